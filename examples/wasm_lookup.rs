@@ -0,0 +1,31 @@
+//! Look up an address, both natively and from a browser.
+//!
+//! Build for the browser with:
+//!
+//! ```sh
+//! cargo build --example wasm_lookup --target wasm32-unknown-unknown
+//! ```
+use pdok_apis::{lookup::LookupClientBuilder, ClientBuilder};
+
+async fn run() {
+    let lookup_client = LookupClientBuilder::new().build();
+
+    match lookup_client.suggest_and_lookup("6512EX", "26").await {
+        Ok(Some(address)) => println!(
+            "{}, {} {}",
+            address.straatnaam, address.postcode, address.woonplaatsnaam
+        ),
+        Ok(None) => println!("no such address"),
+        Err(e) => println!("lookup failed: {e:?}"),
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+fn main() {
+    wasm_bindgen_futures::spawn_local(run());
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn main() {
+    tokio_test::block_on(run());
+}
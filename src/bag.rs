@@ -1,36 +1,96 @@
-use std::{cmp::Ordering, time::Duration};
+use std::{borrow::Cow, cmp::Ordering, collections::BTreeSet, time::Duration};
 
 use crate::{
+    geometry::{geojson_geometry_to_geo, geojson_value_to_polygon},
+    transport::{self, Transport},
+    util::validate_geojson_polygon,
     ClientBuilder,
     Error::{self, *},
 };
 
-use reqwest::Client;
+use chrono::NaiveDate;
+use reqwest::{header::HeaderMap, RequestBuilder};
 use serde::{Deserialize, Serialize};
 
-use geo::Polygon;
+use geo::{MultiPolygon, Point, Polygon, Rect};
 use geojson::Geometry;
 
+/// Client for the BAG individuele bevragingen API.
+///
+/// Cloning is cheap: clones share the underlying connection pool.
+#[derive(Clone)]
 pub struct BagClient {
-    client: Client,
+    client: Transport,
+    base_url: String,
+    peildatum: Option<NaiveDate>,
+    validate_geometry: bool,
 }
 
 pub struct BagClientBuilder<'a> {
     accept_crs: BagCoordinateSpace,
+    accept_format: BagFormat,
+    peildatum: Option<NaiveDate>,
+    validate_geometry: bool,
+    chunked_transfer: bool,
     connection_timeout_secs: u64,
     request_timeout_secs: u64,
+    follow_redirects: bool,
+    max_concurrency: usize,
+    rate_limit_retries: u32,
+    max_retry_after: Duration,
     user_agent: &'a str,
-    api_key: &'a str,
+    user_agent_suffix: bool,
+    api_key: Cow<'a, str>,
+    base_url: &'a str,
+    headers: HeaderMap,
+    #[cfg(feature = "cache")]
+    cache_capacity: usize,
+    #[cfg(feature = "cache")]
+    cache_ttl: Option<Duration>,
 }
 
 impl<'a> BagClientBuilder<'a> {
-    pub fn new(user_agent: &'a str, api_key: &'a str) -> Self {
+    /// The environment variable [`BagClientBuilder::from_env`] reads the API key from
+    pub const API_KEY_VAR: &'static str = "BAG_API_KEY";
+
+    pub fn new(api_key: &'a str) -> Self {
+        Self::with_api_key(Cow::Borrowed(api_key))
+    }
+
+    /// Read the API key from the `BAG_API_KEY` environment variable.
+    pub fn from_env() -> Result<Self, Error> {
+        Self::from_env_var(Self::API_KEY_VAR)
+    }
+
+    /// Read the API key from the given environment variable.
+    pub fn from_env_var(name: &str) -> Result<Self, Error> {
+        let api_key = std::env::var(name).map_err(|_| MissingEnvVar(name.to_string()))?;
+
+        Ok(Self::with_api_key(Cow::Owned(api_key)))
+    }
+
+    fn with_api_key(api_key: Cow<'a, str>) -> Self {
         Self {
-            user_agent,
+            user_agent: crate::DEFAULT_USER_AGENT,
+            user_agent_suffix: true,
             api_key,
+            base_url: BagClient::BAG_URL,
+            headers: HeaderMap::new(),
+            #[cfg(feature = "cache")]
+            cache_capacity: 0,
+            #[cfg(feature = "cache")]
+            cache_ttl: None,
+            chunked_transfer: false,
             connection_timeout_secs: 5,
             request_timeout_secs: 20,
+            follow_redirects: true,
+            max_concurrency: 0,
+            rate_limit_retries: 0,
+            max_retry_after: Duration::from_secs(60),
             accept_crs: BagCoordinateSpace::Rijksdriehoek,
+            accept_format: BagFormat::Hal,
+            peildatum: None,
+            validate_geometry: false,
         }
     }
 
@@ -38,26 +98,52 @@ impl<'a> BagClientBuilder<'a> {
         self.accept_crs = accept_crs;
         self
     }
-}
 
-impl<'a> ClientBuilder<'a> for BagClientBuilder<'a> {
-    type OutputType = BagClient;
-    fn connection_timeout_secs(&mut self, connection_timeout_secs: u64) -> &mut Self {
-        self.connection_timeout_secs = connection_timeout_secs;
+    /// The representation to request through the `Accept` header, see [`BagFormat`].
+    pub fn accept_format(&mut self, accept_format: BagFormat) -> &mut Self {
+        self.accept_format = accept_format;
         self
     }
 
-    fn request_timeout_secs(&mut self, request_timeout_secs: u64) -> &mut Self {
-        self.request_timeout_secs = request_timeout_secs;
+    /// Query the state of the BAG as it was on the given reference date (`geldigOp`),
+    /// instead of the current state.
+    pub fn peildatum(&mut self, peildatum: NaiveDate) -> &mut Self {
+        self.peildatum = Some(peildatum);
         self
     }
 
-    fn build(&self) -> Self::OutputType {
-        use reqwest::header::{HeaderMap, HeaderValue};
+    /// Check the geometry of every pand with [`crate::util::validate_geojson_polygon`],
+    /// failing with [`Error::InvalidGeometry`] instead of returning a broken pand.
+    /// Off by default.
+    pub fn validate_geometry(&mut self, validate_geometry: bool) -> &mut Self {
+        self.validate_geometry = validate_geometry;
+        self
+    }
+
+    /// Send a `transfer-encoding: chunked` header with every request (off by default).
+    ///
+    /// Earlier versions always sent this header to work around a proxy that required it.
+    /// GET requests have no body, so other proxies may reject requests that carry it.
+    pub fn chunked_transfer(&mut self, chunked_transfer: bool) -> &mut Self {
+        self.chunked_transfer = chunked_transfer;
+        self
+    }
+
+    /// Build the client, failing with [`Error::MissingApiKey`] when the API key is empty,
+    /// instead of building a client whose requests will all be refused.
+    pub fn try_build(&self) -> Result<BagClient, Error> {
+        use reqwest::header::HeaderValue;
+
+        if self.api_key.trim().is_empty() {
+            return Err(MissingApiKey);
+        }
+
+        let api_key = HeaderValue::from_str(&self.api_key)
+            .map_err(|_| InvalidHeader("X-Api-Key".to_string()))?;
 
         let mut headers = HeaderMap::new();
 
-        headers.insert("X-Api-Key", HeaderValue::from_str(self.api_key).unwrap());
+        headers.insert("X-Api-Key", api_key);
 
         // Gewenste coördinatenstelsel (CRS) van de coördinaten in de response.
         headers.insert(
@@ -66,31 +152,136 @@ impl<'a> ClientBuilder<'a> for BagClientBuilder<'a> {
         );
 
         headers.insert(
-            "transfer-encoding",
-            HeaderValue::from_str("chunked").unwrap(),
+            reqwest::header::ACCEPT,
+            HeaderValue::from_static(self.accept_format.as_str()),
         );
 
-        let client = reqwest::ClientBuilder::new()
-            .user_agent(self.user_agent)
-            .default_headers(headers)
-            .connect_timeout(Duration::from_secs(self.connection_timeout_secs))
-            .timeout(Duration::new(self.request_timeout_secs, 0))
-            .build()
-            .unwrap();
+        if self.chunked_transfer {
+            headers.insert("transfer-encoding", HeaderValue::from_static("chunked"));
+        }
+
+        headers.extend(self.headers.clone());
+
+        let client_builder = transport::client_builder(
+            self.user_agent,
+            self.user_agent_suffix,
+            headers,
+            self.connection_timeout_secs,
+            self.request_timeout_secs,
+            self.follow_redirects,
+        );
+
+        let client = Transport::new(client_builder.build().unwrap())
+            .with_max_concurrency(std::num::NonZeroUsize::new(self.max_concurrency))
+            .with_rate_limit_retries(self.rate_limit_retries, self.max_retry_after);
+
+        #[cfg(feature = "cache")]
+        let client = client.with_cache(self.cache_capacity, self.cache_ttl);
+
+        Ok(BagClient {
+            client,
+            base_url: self.base_url.to_string(),
+            peildatum: self.peildatum,
+            validate_geometry: self.validate_geometry,
+        })
+    }
+}
+
+impl<'a> ClientBuilder<'a> for BagClientBuilder<'a> {
+    type OutputType = BagClient;
+    fn connection_timeout_secs(&mut self, connection_timeout_secs: u64) -> &mut Self {
+        self.connection_timeout_secs = connection_timeout_secs;
+        self
+    }
+
+    fn request_timeout_secs(&mut self, request_timeout_secs: u64) -> &mut Self {
+        self.request_timeout_secs = request_timeout_secs;
+        self
+    }
+
+    fn follow_redirects(&mut self, follow_redirects: bool) -> &mut Self {
+        self.follow_redirects = follow_redirects;
+        self
+    }
+
+    fn max_concurrency(&mut self, max_concurrency: usize) -> &mut Self {
+        self.max_concurrency = max_concurrency;
+        self
+    }
+
+    fn rate_limit_retries(&mut self, rate_limit_retries: u32) -> &mut Self {
+        self.rate_limit_retries = rate_limit_retries;
+        self
+    }
+
+    fn max_retry_after(&mut self, max_retry_after: Duration) -> &mut Self {
+        self.max_retry_after = max_retry_after;
+        self
+    }
+
+    fn user_agent(&mut self, user_agent: &'a str) -> &mut Self {
+        self.user_agent = user_agent;
+        self
+    }
+
+    fn user_agent_suffix(&mut self, user_agent_suffix: bool) -> &mut Self {
+        self.user_agent_suffix = user_agent_suffix;
+        self
+    }
+
+    fn header(&mut self, name: &str, value: &str) -> Result<&mut Self, Error> {
+        let (name, value) = crate::parse_header(name, value)?;
+        self.headers.insert(name, value);
+        Ok(self)
+    }
+
+    fn base_url(&mut self, base_url: &'a str) -> &mut Self {
+        self.base_url = base_url;
+        self
+    }
+
+    #[cfg(feature = "cache")]
+    fn cache_capacity(&mut self, capacity: usize) -> &mut Self {
+        self.cache_capacity = capacity;
+        self
+    }
+
+    #[cfg(feature = "cache")]
+    fn cache_ttl(&mut self, ttl: Duration) -> &mut Self {
+        self.cache_ttl = Some(ttl);
+        self
+    }
 
-        BagClient { client }
+    /// Build the client, see [`BagClientBuilder::try_build`].
+    ///
+    /// # Panics
+    ///
+    /// When the API key is empty or not a valid header value.
+    fn build(&self) -> Self::OutputType {
+        self.try_build().expect("invalid BAG API key")
     }
 }
 
 impl BagClient {
     const BAG_URL: &'static str = "https://api.bag.kadaster.nl/lvbag/individuelebevragingen/v2";
 
+    ///
+    /// Start a GET request, restricted to the peildatum if one is configured
+    ///
+    fn get(&self, url: &str) -> RequestBuilder {
+        let request = self.client.get(url);
+
+        match self.peildatum {
+            Some(peildatum) => request.query(&[("geldigOp", peildatum.to_string())]),
+            None => request,
+        }
+    }
+
     ///
     /// Fetch embedded links from a BAG call
     ///
     async fn get_link(&self, url: &str) -> Result<Building, Error> {
-        let client_response = self.client.get(url).send().await.map_err(NetworkProblem)?;
-        let response: Building = client_response.json().await.map_err(JsonProblem)?;
+        let response: Building = self.client.send_json(self.get(url)).await?;
 
         Ok(response)
     }
@@ -98,22 +289,342 @@ impl BagClient {
     ///
     /// Fetch all ids for panden, associated with the given addresseerbaarobject
     ///
+    /// An object that BAG does not know yields no panden; failed requests are errors.
+    ///
     pub async fn get_panden(&self, object_id: &str) -> Result<Vec<Pand>, Error> {
-        let url = format!("{}/verblijfsobjecten/{}", BagClient::BAG_URL, object_id);
+        let url = format!("{}/verblijfsobjecten/{}", self.base_url, object_id);
 
-        let client_response = self
-            .client
+        let request = self
             .get(url.as_str())
-            .header("Accept-Crs", "epsg:28992".to_string())
-            .send()
-            .await;
+            .header("Accept-Crs", "epsg:28992".to_string());
 
-        match client_response {
+        match self.client.send(request).await {
             Ok(response) => Ok(self.decode_verblijfsobjecten(response).await?),
-            Err(_) => Ok(vec![]),
+            Err(HttpStatus { code: 404, .. } | EmptyResponse) => Ok(vec![]),
+            Err(e) => Err(e),
         }
     }
 
+    ///
+    /// Fetch a single pand by its BAG identificatie
+    ///
+    pub async fn get_pand(&self, pand_id: &str) -> Result<BuildingEmbedded, Error> {
+        let url = format!("{}/panden/{}", self.base_url, pand_id);
+        let building = self.get_link(&url).await?;
+
+        Ok(building.pand)
+    }
+
+    ///
+    /// Fetch the panden of several addresseerbare objecten at once,
+    /// returning each pand only once, sorted by identificatiecode
+    ///
+    pub async fn get_unique_panden(&self, object_ids: &[&str]) -> Result<Vec<Pand>, Error> {
+        self.get_unique_panden_with_progress(object_ids, |_, _| {})
+            .await
+    }
+
+    ///
+    /// Fetch the panden of several addresseerbare objecten like
+    /// [`BagClient::get_unique_panden`], calling `progress` with the number of objects done
+    /// and the total number of objects as each object completes, e.g. to show a progress bar
+    ///
+    pub async fn get_unique_panden_with_progress(
+        &self,
+        object_ids: &[&str],
+        progress: impl Fn(usize, usize),
+    ) -> Result<Vec<Pand>, Error> {
+        let requests = object_ids
+            .iter()
+            .map(|object_id| self.get_panden(object_id));
+        let responses = crate::util::try_join_all_with_progress(requests, progress).await?;
+
+        let mut panden: Vec<Pand> = responses.into_iter().flatten().collect();
+        panden.sort();
+        panden.dedup();
+
+        Ok(panden)
+    }
+
+    ///
+    /// Sum the area of all panden associated with the given addresseerbaarobject, in m².
+    ///
+    /// Note that each pand is counted in full, even when the object only occupies part of it.
+    ///
+    pub async fn total_pandvlak(&self, object_id: &str) -> Result<f64, Error> {
+        let panden = self.get_panden(object_id).await?;
+
+        Ok(panden
+            .iter()
+            .filter_map(|pand| pand.pandvlak.parse::<f64>().ok())
+            .sum())
+    }
+
+    ///
+    /// The combined outline of all panden associated with the given addresseerbaarobject,
+    /// in rijksdriehoek coordinates. The geometric counterpart of [`BagClient::total_pandvlak`].
+    ///
+    /// Yields `None` when the object has no panden.
+    ///
+    pub async fn building_footprint(
+        &self,
+        object_id: &str,
+    ) -> Result<Option<MultiPolygon<f64>>, Error> {
+        let panden = self.get_panden(object_id).await?;
+
+        let polygons = panden
+            .iter()
+            .map(|pand| geojson_value_to_polygon(&pand.geometry.value))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(GeometryError)?;
+
+        Ok(crate::util::union_polygons(&polygons))
+    }
+
+    ///
+    /// Search the addresses with the given postal code and housenumber, without going
+    /// through the locatieserver. Yields every huisletter and toevoeging of the housenumber.
+    ///
+    pub async fn search_adressen(
+        &self,
+        postcode: &str,
+        huisnummer: u32,
+    ) -> Result<Vec<Adres>, Error> {
+        #[derive(Deserialize)]
+        struct AdressenResponse {
+            #[serde(rename = "_embedded", default)]
+            embedded: Option<Embedded>,
+        }
+
+        #[derive(Deserialize)]
+        struct Embedded {
+            adressen: Vec<Adres>,
+        }
+
+        let url = format!("{}/adressen", self.base_url);
+        let postcode = postcode.replace(' ', "").to_uppercase();
+
+        let request = self.get(&url).query(&[
+            ("postcode", postcode),
+            ("huisnummer", huisnummer.to_string()),
+        ]);
+
+        let response: AdressenResponse = self.client.send_json(request).await?;
+
+        Ok(response
+            .embedded
+            .map(|embedded| embedded.adressen)
+            .unwrap_or_default())
+    }
+
+    ///
+    /// Fetch the verblijfsobjecten (units) within the given pand, e.g. the apartments
+    /// of an apartment building. The reverse of [`BagClient::get_panden`].
+    ///
+    pub async fn get_verblijfsobjecten_for_pand(
+        &self,
+        pand_id: &str,
+    ) -> Result<Vec<Verblijfsobject>, Error> {
+        #[derive(Deserialize)]
+        struct VerblijfsobjectenResponse {
+            #[serde(rename = "_embedded", default)]
+            embedded: Option<Embedded>,
+        }
+
+        #[derive(Deserialize)]
+        struct Embedded {
+            verblijfsobjecten: Vec<Item>,
+        }
+
+        #[derive(Deserialize)]
+        struct Item {
+            verblijfsobject: Verblijfsobject,
+        }
+
+        const PAGE_SIZE: usize = 100;
+
+        let url = format!("{}/verblijfsobjecten", self.base_url);
+        let mut verblijfsobjecten = Vec::new();
+
+        for page in 1.. {
+            let request = self.get(&url).query(&[
+                ("pandIdentificatie", pand_id.to_string()),
+                ("page", page.to_string()),
+                ("pageSize", PAGE_SIZE.to_string()),
+            ]);
+
+            let response: VerblijfsobjectenResponse = self.client.send_json(request).await?;
+
+            let items = response
+                .embedded
+                .map(|embedded| embedded.verblijfsobjecten)
+                .unwrap_or_default();
+            let returned = items.len();
+
+            verblijfsobjecten.extend(items.into_iter().map(|item| item.verblijfsobject));
+
+            if returned < PAGE_SIZE {
+                break;
+            }
+        }
+
+        Ok(verblijfsobjecten)
+    }
+
+    ///
+    /// Fetch an openbare ruimte (usually a street) by its identificatie, e.g. the
+    /// `openbare_ruimte_identificatie` of an [`Adres`].
+    ///
+    pub async fn get_openbareruimte(&self, id: &str) -> Result<OpenbareRuimte, Error> {
+        #[derive(Deserialize)]
+        struct OpenbareRuimteResponse {
+            #[serde(rename = "openbareRuimte")]
+            openbare_ruimte: OpenbareRuimte,
+        }
+
+        let url = format!("{}/openbareruimten/{}", self.base_url, id);
+        let response: OpenbareRuimteResponse = self.client.send_json(self.get(&url)).await?;
+
+        Ok(response.openbare_ruimte)
+    }
+
+    ///
+    /// Fetch a woonplaats by its identificatie, e.g. the `woonplaats_identificatie` of an
+    /// [`Adres`] or [`OpenbareRuimte`], including its outline.
+    ///
+    pub async fn get_woonplaats(&self, id: &str) -> Result<Woonplaats, Error> {
+        #[derive(Deserialize)]
+        struct WoonplaatsResponse {
+            woonplaats: Woonplaats,
+            #[serde(rename = "_embedded", default)]
+            embedded: Option<Embedded>,
+        }
+
+        #[derive(Deserialize)]
+        struct Embedded {
+            geometrie: Option<Geometry>,
+        }
+
+        let url = format!("{}/woonplaatsen/{}", self.base_url, id);
+        let request = self.get(&url).query(&[("expand", "geometrie")]);
+        let response: WoonplaatsResponse = self.client.send_json(request).await?;
+
+        // The expanded geometry may be embedded rather than part of the woonplaats
+        let mut woonplaats = response.woonplaats;
+        if woonplaats.geometry.is_none() {
+            woonplaats.geometry = response.embedded.and_then(|embedded| embedded.geometrie);
+        }
+
+        Ok(woonplaats)
+    }
+
+    ///
+    /// Fetch the panden within the given bounding box in rijksdriehoek coordinates, e.g. to
+    /// draw all buildings in view on a map. The geometries are in the coordinate space the
+    /// client was built with.
+    ///
+    /// BAG only accepts boxes up to 250,000 m², so a larger box is split into tiles, which
+    /// costs a request (or more, for crowded tiles) per tile.
+    ///
+    pub async fn get_panden_in_bbox(
+        &self,
+        bbox: Rect<f64>,
+    ) -> Result<Vec<BuildingEmbedded>, Error> {
+        #[derive(Deserialize)]
+        struct PandenResponse {
+            #[serde(rename = "_embedded", default)]
+            embedded: Option<Embedded>,
+        }
+
+        #[derive(Deserialize)]
+        struct Embedded {
+            panden: Vec<Building>,
+        }
+
+        const PAGE_SIZE: usize = 100;
+        const MAX_BBOX_AREA: f64 = 250_000.0;
+
+        let url = format!("{}/panden", self.base_url);
+        let tiles_per_side = (bbox.width() * bbox.height() / MAX_BBOX_AREA)
+            .sqrt()
+            .ceil()
+            .max(1.0) as usize;
+        let tile_width = bbox.width() / tiles_per_side as f64;
+        let tile_height = bbox.height() / tiles_per_side as f64;
+
+        let mut seen = BTreeSet::new();
+        let mut panden = Vec::new();
+
+        for column in 0..tiles_per_side {
+            for row in 0..tiles_per_side {
+                let min_x = bbox.min().x + column as f64 * tile_width;
+                let min_y = bbox.min().y + row as f64 * tile_height;
+                let tile = format!(
+                    "{},{},{},{}",
+                    min_x,
+                    min_y,
+                    min_x + tile_width,
+                    min_y + tile_height
+                );
+
+                for page in 1.. {
+                    let request = self
+                        .get(&url)
+                        .query(&[
+                            ("bbox", tile.clone()),
+                            ("page", page.to_string()),
+                            ("pageSize", PAGE_SIZE.to_string()),
+                        ])
+                        .header("Content-Crs", "epsg:28992");
+
+                    let response: PandenResponse = self.client.send_json(request).await?;
+
+                    let items = response
+                        .embedded
+                        .map(|embedded| embedded.panden)
+                        .unwrap_or_default();
+                    let returned = items.len();
+
+                    // Panden on the border of two tiles are returned for both
+                    for item in items {
+                        if seen.insert(item.pand.identificatie.clone()) {
+                            panden.push(item.pand);
+                        }
+                    }
+
+                    if returned < PAGE_SIZE {
+                        break;
+                    }
+                }
+            }
+        }
+
+        Ok(panden)
+    }
+
+    ///
+    /// The largest pand associated with the given addresseerbaarobject, by pandvlak,
+    /// e.g. to show the main building of an address
+    ///
+    pub async fn get_primary_pand(&self, object_id: &str) -> Result<Option<Pand>, Error> {
+        let panden = self.get_panden(object_id).await?;
+
+        Ok(panden.into_iter().max_by(|a, b| {
+            let area = |pand: &Pand| pand.pandvlak.parse::<f64>().unwrap_or(0.0);
+            area(a).total_cmp(&area(b))
+        }))
+    }
+
+    ///
+    /// The distinct statuses of the panden associated with the given addresseerbaarobject,
+    /// e.g. to check whether any of them has been demolished
+    ///
+    pub async fn pand_statuses(&self, object_id: &str) -> Result<BTreeSet<PandStatus>, Error> {
+        let panden = self.get_panden(object_id).await?;
+
+        Ok(panden.iter().map(Pand::pand_status).collect())
+    }
+
     ///
     /// Get bag status by fetch info about a random pand.
     ///
@@ -127,6 +638,24 @@ impl BagClient {
         }
     }
 
+    ///
+    /// Check if the API is up by fetching a single verblijfsobject, failing when it takes
+    /// longer than `timeout` regardless of the configured request timeout.
+    ///
+    /// The cache is bypassed. Yields `false` when the API responds with an error status,
+    /// and fails on network problems and timeouts.
+    ///
+    pub async fn probe(&self, timeout: Duration) -> Result<bool, Error> {
+        let url = format!("{}/verblijfsobjecten/0268010000084126", self.base_url);
+
+        let response = self
+            .client
+            .probe(transport::with_timeout(self.get(&url), timeout))
+            .await?;
+
+        Ok(response.is_some())
+    }
+
     async fn decode_verblijfsobjecten(
         &self,
         response: reqwest::Response,
@@ -158,10 +687,7 @@ impl BagClient {
             gebruiksdoelen: Vec<String>,
         }
 
-        let decoded = response
-            .json::<VerblijfsObjectResponse>()
-            .await
-            .map_err(JsonProblem)?;
+        let decoded = transport::decode_json::<VerblijfsObjectResponse>(response).await?;
 
         let VerblijfsObjectResponse {
             verblijfsobject,
@@ -182,7 +708,16 @@ impl BagClient {
         for pand in panden {
             let building = self.get_link(&pand.href).await?;
             let geometry_json_value = &building.pand.geometry.value;
-            let polygon: Polygon<f64> = geojson_value_to_polygon(geometry_json_value).unwrap();
+
+            if self.validate_geometry {
+                validate_geojson_polygon(geometry_json_value).map_err(|error| InvalidGeometry {
+                    id: building.pand.identificatie.clone(),
+                    error,
+                })?;
+            }
+
+            let polygon: Polygon<f64> =
+                geojson_value_to_polygon(geometry_json_value).map_err(GeometryError)?;
 
             let pand = Pand {
                 identificatiecode: building.pand.identificatie,
@@ -204,11 +739,22 @@ impl BagClient {
 
 /// Coordinate space that the BAG returns
 /// (currently only Rijksdriehoek is supported)
+///
+/// (De)serializes as its EPSG code, e.g. `"epsg:28992"`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum BagCoordinateSpace {
     Rijksdriehoek,
 }
 
 impl BagCoordinateSpace {
+    /// Parse an EPSG code, e.g. `"epsg:28992"`
+    pub fn from_epsg(epsg: &str) -> Option<Self> {
+        match epsg.to_ascii_lowercase().as_str() {
+            "epsg:28992" => Some(BagCoordinateSpace::Rijksdriehoek),
+            _ => None,
+        }
+    }
+
     fn as_str(&self) -> &'static str {
         match self {
             BagCoordinateSpace::Rijksdriehoek => {
@@ -219,6 +765,42 @@ impl BagCoordinateSpace {
     }
 }
 
+impl Serialize for BagCoordinateSpace {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for BagCoordinateSpace {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let epsg = String::deserialize(deserializer)?;
+        BagCoordinateSpace::from_epsg(&epsg)
+            .ok_or_else(|| serde::de::Error::custom(format!("unsupported crs: {epsg}")))
+    }
+}
+
+/// The representation the BAG responds with, negotiated through the `Accept` header.
+///
+/// The client relies on the `_links` of HAL responses to find the panden of a
+/// verblijfsobject, hence [`BagFormat::Hal`] is the default. Plain JSON responses may
+/// leave these links out, in which case fetching panden fails with [`Error::DecodeError`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BagFormat {
+    /// `application/hal+json`
+    Hal,
+    /// `application/json`
+    Json,
+}
+
+impl BagFormat {
+    fn as_str(&self) -> &'static str {
+        match self {
+            BagFormat::Hal => "application/hal+json",
+            BagFormat::Json => "application/json",
+        }
+    }
+}
+
 #[derive(Serialize)]
 pub struct BagRequest {
     query: String,
@@ -235,7 +817,7 @@ pub struct Building {
     pand: BuildingEmbedded,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct BuildingEmbedded {
     pub identificatie: String,
     #[serde(rename = "geometrie")]
@@ -248,16 +830,279 @@ pub struct BuildingEmbedded {
 
 #[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct Pand {
+    /// The BAG identificatie, which uniquely identifies the pand
     pub identificatiecode: String,
+    /// The area of the geometry in m², rounded to whole meters
     pub pandvlak: String,
+    /// The floor area of the verblijfsobject the pand was fetched for, in m²
     pub vloeroppervlak: String,
     pub bouwjaar: String,
     pub pandstatus: String,
+    /// The status of the verblijfsobject the pand was fetched for
     pub objectstatus: String,
+    /// The gebruiksdoelen of the verblijfsobject the pand was fetched for, comma separated
     pub gebruiksdoel: String,
+    /// The outline of the pand, in the coordinate space it was fetched in
     pub geometry: Geometry,
 }
 
+impl Pand {
+    /// Construct a pand without fetching it, e.g. for testing code that consumes panden.
+    ///
+    /// The `pandvlak` is derived from the geometry, the remaining fields are left empty
+    /// and can be set directly.
+    ///
+    /// ```
+    /// use pdok_apis::bag::Pand;
+    ///
+    /// let geometry = geojson::Geometry::new(geojson::Value::Polygon(vec![vec![
+    ///     vec![0.0, 0.0],
+    ///     vec![10.0, 0.0],
+    ///     vec![10.0, 10.0],
+    ///     vec![0.0, 10.0],
+    ///     vec![0.0, 0.0],
+    /// ]]));
+    /// let pand = Pand::from_parts("0268100000317781", geometry);
+    ///
+    /// assert_eq!(pand.pandvlak, "100");
+    /// assert_eq!(pand.centroid().unwrap().x(), 5.0);
+    /// ```
+    pub fn from_parts(identificatiecode: impl Into<String>, geometry: Geometry) -> Self {
+        use geo::algorithm::area::Area;
+
+        let pandvlak = geojson_value_to_polygon(&geometry.value)
+            .map(|polygon| polygon.unsigned_area().round().to_string())
+            .unwrap_or_default();
+
+        Pand {
+            identificatiecode: identificatiecode.into(),
+            pandvlak,
+            vloeroppervlak: String::new(),
+            bouwjaar: String::new(),
+            pandstatus: String::new(),
+            objectstatus: String::new(),
+            gebruiksdoel: String::new(),
+            geometry,
+        }
+    }
+
+    /// The parsed lifecycle status of the pand.
+    pub fn pand_status(&self) -> PandStatus {
+        PandStatus::from(self.pandstatus.as_str())
+    }
+
+    /// The parsed lifecycle status of the verblijfsobject the pand was fetched for.
+    pub fn object_status(&self) -> ObjectStatus {
+        ObjectStatus::from(self.objectstatus.as_str())
+    }
+
+    /// Whether the pand is standing and in use, and its verblijfsobject has not been
+    /// withdrawn.
+    ///
+    /// An unknown (e.g. empty) object status does not make a pand inactive.
+    pub fn is_active(&self) -> bool {
+        self.pand_status().is_active() && !self.object_status().is_withdrawn()
+    }
+
+    /// The pand as a GeoJSON feature, with its other fields as properties.
+    pub fn to_feature(&self) -> geojson::Feature {
+        crate::geometry::to_feature(self, &self.identificatiecode, &self.geometry)
+    }
+
+    /// The geometry as WKT, normally a `POLYGON`. The coordinates are in the CRS of the
+    /// client's `accept_crs` at the time the pand was fetched. Fails when the geometry
+    /// contains a malformed position.
+    pub fn to_wkt(&self) -> Result<String, crate::geometry::GeometryError> {
+        crate::geometry::geojson_geometry_to_wkt(&self.geometry)
+    }
+
+    /// The centroid of the pand's geometry, in the coordinate space it was fetched in.
+    pub fn centroid(&self) -> Option<Point<f64>> {
+        use geo::algorithm::centroid::Centroid;
+
+        geojson_geometry_to_geo(&self.geometry).ok()?.centroid()
+    }
+}
+
+/// An address from the BAG adressen endpoint, see [`BagClient::search_adressen`]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Adres {
+    pub openbare_ruimte_naam: String,
+    #[serde(default)]
+    pub korte_naam: Option<String>,
+    pub huisnummer: u32,
+    #[serde(default)]
+    pub huisletter: Option<String>,
+    #[serde(default)]
+    pub huisnummertoevoeging: Option<String>,
+    #[serde(default)]
+    pub postcode: Option<String>,
+    pub woonplaats_naam: String,
+    pub nummeraanduiding_identificatie: String,
+    #[serde(default)]
+    pub openbare_ruimte_identificatie: Option<String>,
+    #[serde(default)]
+    pub woonplaats_identificatie: Option<String>,
+    /// The verblijfsobject, standplaats or ligplaats, for use in [`BagClient::get_panden`]
+    pub adresseerbaar_object_identificatie: String,
+    #[serde(default)]
+    pub pand_identificaties: Vec<String>,
+}
+
+/// A street, square or other public space that addresses are named after, see
+/// [`BagClient::get_openbareruimte`]
+///
+/// BAG does not register the outline of an openbare ruimte.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct OpenbareRuimte {
+    pub identificatie: String,
+    pub naam: String,
+    /// A shortened name of at most 24 characters, for long names
+    #[serde(default)]
+    pub korte_naam: Option<String>,
+    /// The kind of openbare ruimte, e.g. `Weg` or `Water`
+    #[serde(rename = "type", default)]
+    pub openbare_ruimte_type: String,
+    #[serde(default)]
+    pub status: String,
+    /// The woonplaats the openbare ruimte lies in, for use in [`BagClient::get_woonplaats`]
+    #[serde(rename = "ligtIn")]
+    pub woonplaats_identificatie: String,
+}
+
+/// A city, town or village, see [`BagClient::get_woonplaats`]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Woonplaats {
+    pub identificatie: String,
+    pub naam: String,
+    #[serde(default)]
+    pub status: String,
+    /// The outline of the woonplaats, in the coordinate space the client was built with.
+    /// Often large, as it follows the boundary in detail.
+    #[serde(rename = "geometrie", default)]
+    pub geometry: Option<Geometry>,
+}
+
+/// A unit within a pand, see [`BagClient::get_verblijfsobjecten_for_pand`]
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct Verblijfsobject {
+    /// The BAG identificatie, for use in [`BagClient::get_panden`]
+    pub identificatie: String,
+    /// The floor area in m²
+    #[serde(default)]
+    pub oppervlakte: i64,
+    #[serde(default)]
+    pub gebruiksdoelen: Vec<String>,
+    #[serde(default)]
+    pub status: String,
+}
+
+impl Verblijfsobject {
+    /// The parsed lifecycle status of the verblijfsobject.
+    pub fn object_status(&self) -> ObjectStatus {
+        ObjectStatus::from(self.status.as_str())
+    }
+}
+
+/// Normalize a BAG status for comparison, ignoring casing and superfluous whitespace
+fn normalize_status(status: &str) -> String {
+    status
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// The lifecycle status of a pand in the BAG
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PandStatus {
+    BouwvergunningVerleend,
+    NietGerealiseerd,
+    BouwGestart,
+    InGebruikNietIngemeten,
+    InGebruik,
+    Verbouwing,
+    SloopvergunningVerleend,
+    Gesloopt,
+    BuitenGebruik,
+    TenOnrechteOpgevoerd,
+    /// A status not known to this crate, as it was received
+    Other(String),
+}
+
+impl PandStatus {
+    /// Whether the pand is standing and in use
+    pub fn is_active(&self) -> bool {
+        matches!(
+            self,
+            PandStatus::InGebruik | PandStatus::InGebruikNietIngemeten | PandStatus::Verbouwing
+        )
+    }
+}
+
+impl From<&str> for PandStatus {
+    fn from(status: &str) -> Self {
+        match normalize_status(status).as_str() {
+            "bouwvergunning verleend" => PandStatus::BouwvergunningVerleend,
+            "niet gerealiseerd pand" => PandStatus::NietGerealiseerd,
+            "bouw gestart" => PandStatus::BouwGestart,
+            "pand in gebruik (niet ingemeten)" => PandStatus::InGebruikNietIngemeten,
+            "pand in gebruik" => PandStatus::InGebruik,
+            "verbouwing pand" => PandStatus::Verbouwing,
+            "sloopvergunning verleend" => PandStatus::SloopvergunningVerleend,
+            "pand gesloopt" => PandStatus::Gesloopt,
+            "pand buiten gebruik" => PandStatus::BuitenGebruik,
+            "pand ten onrechte opgevoerd" => PandStatus::TenOnrechteOpgevoerd,
+            _ => PandStatus::Other(status.to_string()),
+        }
+    }
+}
+
+/// The lifecycle status of a verblijfsobject in the BAG
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ObjectStatus {
+    Gevormd,
+    NietGerealiseerd,
+    InGebruikNietIngemeten,
+    InGebruik,
+    Verbouwing,
+    Ingetrokken,
+    BuitenGebruik,
+    TenOnrechteOpgevoerd,
+    /// A status not known to this crate, as it was received
+    Other(String),
+}
+
+impl ObjectStatus {
+    /// Whether the verblijfsobject was withdrawn, never realized or registered in error
+    pub fn is_withdrawn(&self) -> bool {
+        matches!(
+            self,
+            ObjectStatus::NietGerealiseerd
+                | ObjectStatus::Ingetrokken
+                | ObjectStatus::TenOnrechteOpgevoerd
+        )
+    }
+}
+
+impl From<&str> for ObjectStatus {
+    fn from(status: &str) -> Self {
+        match normalize_status(status).as_str() {
+            "verblijfsobject gevormd" => ObjectStatus::Gevormd,
+            "niet gerealiseerd verblijfsobject" => ObjectStatus::NietGerealiseerd,
+            "verblijfsobject in gebruik (niet ingemeten)" => ObjectStatus::InGebruikNietIngemeten,
+            "verblijfsobject in gebruik" => ObjectStatus::InGebruik,
+            "verbouwing verblijfsobject" => ObjectStatus::Verbouwing,
+            "verblijfsobject ingetrokken" => ObjectStatus::Ingetrokken,
+            "verblijfsobject buiten gebruik" => ObjectStatus::BuitenGebruik,
+            "verblijfsobject ten onrechte opgevoerd" => ObjectStatus::TenOnrechteOpgevoerd,
+            _ => ObjectStatus::Other(status.to_string()),
+        }
+    }
+}
+
 impl PartialEq for Pand {
     fn eq(&self, other: &Self) -> bool {
         self.identificatiecode == other.identificatiecode
@@ -266,6 +1111,12 @@ impl PartialEq for Pand {
 
 impl Eq for Pand {}
 
+impl std::hash::Hash for Pand {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.identificatiecode.hash(state);
+    }
+}
+
 impl PartialOrd for Pand {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -284,41 +1135,8 @@ impl Ord for Pand {
     }
 }
 
-fn linestring_help(value: &[geojson::Position]) -> geo::LineString<f64> {
-    let mut points = Vec::with_capacity(value.len());
-
-    for position in value {
-        match position[..] {
-            [x, y] | [x, y, _] => {
-                points.push((x, y));
-            }
-            _ => panic!("invalid positions for a polygon"),
-        }
-    }
-
-    geo::LineString::from(points)
-}
-
-fn geojson_value_to_polygon(value: &geojson::Value) -> Option<Polygon<f64>> {
-    use geojson::Value::*;
-
-    match value {
-        Polygon(rings) => match rings.split_first() {
-            None => unreachable!(),
-            Some((outer_positions, inner_positions)) => {
-                let outer = linestring_help(outer_positions);
-
-                let inners: Vec<_> = inner_positions.iter().map(|x| linestring_help(x)).collect();
-
-                Some(geo::Polygon::new(outer, inners))
-            }
-        },
-        _ => None,
-    }
-}
-
 #[cfg(test)]
-mod test {
+pub(crate) mod test {
 
     use super::*;
 
@@ -328,16 +1146,40 @@ mod test {
         };
     }
 
-    pub fn get_bag_key() -> String {
+    pub(crate) fn get_bag_key() -> String {
         std::env::var("BAG_API_KEY").expect("Environment variable missing: BAG_API_KEY")
     }
 
-    const VERSION: &str = env!("CARGO_PKG_VERSION");
+    #[test]
+    fn test_from_env() {
+        let missing = BagClientBuilder::from_env_var("PDOK_APIS_TEST_UNSET_KEY");
+        assert!(matches!(missing, Err(MissingEnvVar(name)) if name == "PDOK_APIS_TEST_UNSET_KEY"));
+
+        std::env::set_var("PDOK_APIS_TEST_BAG_KEY", "secret");
+        let builder = BagClientBuilder::from_env_var("PDOK_APIS_TEST_BAG_KEY").unwrap();
+        assert_eq!(builder.api_key, "secret");
+    }
+
+    #[test]
+    fn test_try_build_missing_api_key() {
+        assert!(matches!(
+            BagClientBuilder::new("").try_build(),
+            Err(MissingApiKey)
+        ));
+        assert!(matches!(
+            BagClientBuilder::new("  ").try_build(),
+            Err(MissingApiKey)
+        ));
+        assert!(matches!(
+            BagClientBuilder::new("secret\n").try_build(),
+            Err(InvalidHeader(_))
+        ));
+        assert!(BagClientBuilder::new("secret").try_build().is_ok());
+    }
 
     #[test]
     fn test_get_building_year() {
-        let ua = format!("pdok-apis bag {}", VERSION);
-        let bag_client = BagClientBuilder::new(&ua, &get_bag_key()).build();
+        let bag_client = BagClientBuilder::new(&get_bag_key()).build();
 
         let object_id = "0268010000084126";
         let buildings = aw!(bag_client.get_panden(object_id));
@@ -345,4 +1187,868 @@ mod test {
 
         assert_eq!(year, String::from("2008"));
     }
+
+    #[test]
+    fn test_get_pand() {
+        let bag_client = BagClientBuilder::new(&get_bag_key()).build();
+
+        let object_id = "0268010000084126";
+        let buildings = aw!(bag_client.get_panden(object_id)).unwrap();
+        let pand_id = &buildings.first().unwrap().identificatiecode;
+
+        let pand = aw!(bag_client.get_pand(pand_id)).unwrap();
+
+        assert_eq!(&pand.identificatie, pand_id);
+        assert_eq!(pand.bouwjaar, String::from("2008"));
+    }
+
+    #[test]
+    fn test_get_pand_peildatum() {
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            let uri = server.uri();
+
+            Mock::given(matchers::path("/verblijfsobjecten/0268010000084126"))
+                .and(matchers::query_param("geldigOp", "2020-01-01"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "verblijfsobject": { "gebruiksdoelen": [] },
+                    "_links": { "maaktDeelUitVan": [{ "href": format!("{uri}/panden/1") }] }
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+            // The linked pand is requested at the peildatum as well
+            Mock::given(matchers::path("/panden/1"))
+                .and(matchers::query_param("geldigOp", "2020-01-01"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "pand": {
+                        "identificatie": "1",
+                        "geometrie": {
+                            "type": "Polygon",
+                            "coordinates": [[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 0.0]]]
+                        },
+                        "oorspronkelijkBouwjaar": "1900",
+                        "status": "Pand in gebruik"
+                    }
+                })))
+                .expect(2)
+                .mount(&server)
+                .await;
+            Mock::given(matchers::any())
+                .respond_with(ResponseTemplate::new(500))
+                .expect(0)
+                .mount(&server)
+                .await;
+
+            let bag_client = BagClientBuilder::new("key")
+                .base_url(&uri)
+                .peildatum(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap())
+                .build();
+
+            let panden = bag_client.get_panden("0268010000084126").await.unwrap();
+            assert_eq!(panden.len(), 1);
+
+            let pand = bag_client.get_pand("1").await.unwrap();
+            assert_eq!(pand.bouwjaar, "1900");
+        });
+    }
+
+    #[test]
+    fn test_pand_status() {
+        assert_eq!(PandStatus::from("Pand in gebruik"), PandStatus::InGebruik);
+        assert_eq!(
+            PandStatus::from("  pand IN gebruik (niet  ingemeten) "),
+            PandStatus::InGebruikNietIngemeten
+        );
+        assert_eq!(PandStatus::from("Pand gesloopt"), PandStatus::Gesloopt);
+        assert_eq!(
+            ObjectStatus::from("Verblijfsobject ingetrokken"),
+            ObjectStatus::Ingetrokken
+        );
+        assert_eq!(
+            PandStatus::from("Pand verplaatst"),
+            PandStatus::Other("Pand verplaatst".to_string())
+        );
+
+        let geometry = geojson::Geometry::new(geojson::Value::Point(vec![0.0, 0.0]));
+        let mut pand = Pand::from_parts("0268100000317781", geometry);
+        pand.pandstatus = "Pand in gebruik".to_string();
+        assert!(pand.is_active());
+
+        pand.objectstatus = "Verblijfsobject ingetrokken".to_string();
+        assert!(!pand.is_active());
+
+        pand.objectstatus = "Verblijfsobject in gebruik".to_string();
+        pand.pandstatus = "Pand gesloopt".to_string();
+        assert!(!pand.is_active());
+    }
+
+    #[test]
+    fn test_http_status() {
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(matchers::path("/panden/0268100000317781"))
+                .respond_with(ResponseTemplate::new(403).set_body_string("invalid api key"))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let bag_client = BagClientBuilder::new("invalid").base_url(&uri).build();
+
+            let result = bag_client.get_pand("0268100000317781").await;
+            assert!(matches!(
+                result,
+                Err(HttpStatus { code: 403, body }) if body == "invalid api key"
+            ));
+        });
+    }
+
+    #[test]
+    fn test_malformed_geometry() {
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            let uri = server.uri();
+
+            Mock::given(matchers::path("/verblijfsobjecten/0268010000084126"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "verblijfsobject": { "status": "Verblijfsobject in gebruik", "gebruiksdoelen": [] },
+                    "_links": { "maaktDeelUitVan": [{ "href": format!("{}/panden/0268100000317781", uri) }] }
+                })))
+                .mount(&server)
+                .await;
+            Mock::given(matchers::path("/panden/0268100000317781"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "pand": {
+                        "identificatie": "0268100000317781",
+                        "geometrie": { "type": "Polygon", "coordinates": [[[0.0, 0.0], [1.0, 2.0, 3.0, 4.0]]] },
+                        "oorspronkelijkBouwjaar": "1900",
+                        "status": "Pand in gebruik"
+                    }
+                })))
+                .mount(&server)
+                .await;
+
+            let bag_client = BagClientBuilder::new("key").base_url(&uri).build();
+
+            let result = bag_client.get_panden("0268010000084126").await;
+            assert!(matches!(
+                result,
+                Err(GeometryError(crate::geometry::GeometryError::InvalidPosition(position)))
+                    if position == vec![1.0, 2.0, 3.0, 4.0]
+            ));
+        });
+    }
+
+    #[test]
+    fn test_get_panden_errors() {
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(matchers::path("/verblijfsobjecten/0268010000000404"))
+                .respond_with(ResponseTemplate::new(404))
+                .mount(&server)
+                .await;
+            Mock::given(matchers::path("/verblijfsobjecten/0268010000000500"))
+                .respond_with(ResponseTemplate::new(500))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let bag_client = BagClientBuilder::new("key").base_url(&uri).build();
+
+            let unknown = bag_client.get_panden("0268010000000404").await;
+            assert!(unknown.unwrap().is_empty());
+
+            let failing = bag_client.get_panden("0268010000000500").await;
+            assert!(matches!(failing, Err(HttpStatus { code: 500, .. })));
+
+            // Nothing listens on the discard port
+            let offline = BagClientBuilder::new("key")
+                .base_url("http://127.0.0.1:9")
+                .build();
+            let unreachable = offline.get_panden("0268010000084126").await;
+            assert!(matches!(unreachable, Err(NetworkProblem(_))));
+        });
+    }
+
+    #[test]
+    fn test_total_pandvlak_without_panden() {
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(matchers::path("/verblijfsobjecten/0268010000000001"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "verblijfsobject": { "gebruiksdoelen": [] },
+                    "_links": { "maaktDeelUitVan": [] }
+                })))
+                .mount(&server)
+                .await;
+            Mock::given(matchers::path("/verblijfsobjecten/0268010000000404"))
+                .respond_with(ResponseTemplate::new(404))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let bag_client = BagClientBuilder::new("key").base_url(&uri).build();
+
+            let unlinked = bag_client.total_pandvlak("0268010000000001").await;
+            assert_eq!(unlinked.unwrap(), 0.0);
+
+            let unknown = bag_client.total_pandvlak("0268010000000404").await;
+            assert_eq!(unknown.unwrap(), 0.0);
+        });
+    }
+
+    #[test]
+    fn test_pand_aggregates_errors() {
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(matchers::path("/verblijfsobjecten/0268010000000500"))
+                .respond_with(ResponseTemplate::new(500))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let bag_client = BagClientBuilder::new("key").base_url(&uri).build();
+            let failing = "0268010000000500";
+            let is_500 = |e: &Error| matches!(e, HttpStatus { code: 500, .. });
+
+            assert!(is_500(
+                &bag_client.total_pandvlak(failing).await.unwrap_err()
+            ));
+            assert!(is_500(
+                &bag_client.get_primary_pand(failing).await.unwrap_err()
+            ));
+            assert!(is_500(
+                &bag_client.building_footprint(failing).await.unwrap_err()
+            ));
+            assert!(is_500(
+                &bag_client.pand_statuses(failing).await.unwrap_err()
+            ));
+        });
+    }
+
+    #[test]
+    fn test_no_chunked_transfer_by_default() {
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(wiremock::matchers::any())
+                .respond_with(ResponseTemplate::new(404))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let bag_client = BagClientBuilder::new("key").base_url(&uri).build();
+
+            bag_client.get_panden("0268010000084126").await.unwrap();
+
+            let requests = server.received_requests().await.unwrap();
+            assert!(!requests.is_empty());
+            assert!(requests.iter().all(|request| request
+                .headers
+                .keys()
+                .all(|name| name.as_str() != "transfer-encoding")));
+        });
+    }
+
+    #[test]
+    fn test_accept_format() {
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            let pand = serde_json::json!({
+                "pand": {
+                    "identificatie": "0268100000317781",
+                    "geometrie": { "type": "Point", "coordinates": [0.0, 0.0] },
+                    "oorspronkelijkBouwjaar": "1900",
+                    "status": "Pand in gebruik"
+                }
+            });
+
+            Mock::given(matchers::header("Accept", "application/hal+json"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(&pand))
+                .expect(1)
+                .mount(&server)
+                .await;
+            Mock::given(matchers::header("Accept", "application/json"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(&pand))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let hal_client = BagClientBuilder::new("key").base_url(&uri).build();
+            let json_client = BagClientBuilder::new("key")
+                .base_url(&uri)
+                .accept_format(BagFormat::Json)
+                .build();
+
+            assert!(hal_client.get_pand("0268100000317781").await.is_ok());
+            assert!(json_client.get_pand("0268100000317781").await.is_ok());
+        });
+    }
+
+    #[test]
+    fn test_search_adressen() {
+        let bag_client = BagClientBuilder::new(&get_bag_key()).build();
+
+        let adressen = aw!(bag_client.search_adressen("6512 ex", 26)).unwrap();
+
+        assert!(adressen
+            .iter()
+            .any(|adres| adres.openbare_ruimte_naam == "Castellastraat"
+                && adres.adresseerbaar_object_identificatie == "0268010000084126"));
+    }
+
+    #[test]
+    fn test_search_adressen_mock() {
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(matchers::path("/adressen"))
+                .and(matchers::query_param("postcode", "6512EX"))
+                .and(matchers::query_param("huisnummer", "26"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "_embedded": { "adressen": [{
+                        "openbareRuimteNaam": "Castellastraat",
+                        "korteNaam": "Castellastraat",
+                        "huisnummer": 26,
+                        "postcode": "6512EX",
+                        "woonplaatsNaam": "Nijmegen",
+                        "nummeraanduidingIdentificatie": "0268200000084126",
+                        "openbareRuimteIdentificatie": "0268300000000456",
+                        "woonplaatsIdentificatie": "1152",
+                        "adresseerbaarObjectIdentificatie": "0268010000084126",
+                        "pandIdentificaties": ["0268100000317781"]
+                    }]}
+                })))
+                .mount(&server)
+                .await;
+            Mock::given(matchers::path("/adressen"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({})))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let bag_client = BagClientBuilder::new("key").base_url(&uri).build();
+
+            let adressen = bag_client.search_adressen("6512ex", 26).await.unwrap();
+            assert_eq!(adressen.len(), 1);
+            assert_eq!(
+                adressen[0].adresseerbaar_object_identificatie,
+                "0268010000084126"
+            );
+            assert_eq!(adressen[0].huisletter, None);
+
+            let adressen = bag_client.search_adressen("6512EX", 2600).await.unwrap();
+            assert!(adressen.is_empty());
+        });
+    }
+
+    #[test]
+    fn test_get_verblijfsobjecten_for_pand() {
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        let unit = |id: &str, oppervlakte: i64| {
+            serde_json::json!({
+                "verblijfsobject": {
+                    "identificatie": id,
+                    "oppervlakte": oppervlakte,
+                    "gebruiksdoelen": ["woonfunctie"],
+                    "status": "Verblijfsobject in gebruik"
+                }
+            })
+        };
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(matchers::path("/verblijfsobjecten"))
+                .and(matchers::query_param(
+                    "pandIdentificatie",
+                    "0268100000317781",
+                ))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "_embedded": { "verblijfsobjecten": [
+                        unit("0268010000000001", 54),
+                        unit("0268010000000002", 61),
+                        unit("0268010000000003", 87),
+                    ]}
+                })))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let bag_client = BagClientBuilder::new("key").base_url(&uri).build();
+
+            let units = bag_client
+                .get_verblijfsobjecten_for_pand("0268100000317781")
+                .await
+                .unwrap();
+
+            assert_eq!(units.len(), 3);
+            assert_eq!(units[2].identificatie, "0268010000000003");
+            assert_eq!(units[2].oppervlakte, 87);
+            assert_eq!(units[0].gebruiksdoelen, ["woonfunctie"]);
+            assert_eq!(units[0].object_status(), ObjectStatus::InGebruik);
+        });
+    }
+
+    #[test]
+    fn test_get_openbareruimte_and_woonplaats() {
+        let bag_client = BagClientBuilder::new(&get_bag_key()).build();
+
+        let adres = aw!(bag_client.search_adressen("6512EX", 26))
+            .unwrap()
+            .remove(0);
+
+        let straat =
+            aw!(bag_client
+                .get_openbareruimte(adres.openbare_ruimte_identificatie.as_deref().unwrap()))
+            .unwrap();
+        assert_eq!(straat.naam, "Castellastraat");
+
+        let woonplaats = aw!(bag_client.get_woonplaats(&straat.woonplaats_identificatie)).unwrap();
+        assert_eq!(woonplaats.naam, "Nijmegen");
+        assert!(woonplaats.geometry.is_some());
+    }
+
+    #[test]
+    fn test_get_openbareruimte_and_woonplaats_mock() {
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(matchers::path("/openbareruimten/0268300000002333"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "openbareRuimte": {
+                        "identificatie": "0268300000002333",
+                        "naam": "Castellastraat",
+                        "type": "Weg",
+                        "status": "Naamgeving uitgegeven",
+                        "ligtIn": "1152"
+                    }
+                })))
+                .mount(&server)
+                .await;
+            Mock::given(matchers::path("/woonplaatsen/1152"))
+                .and(matchers::query_param("expand", "geometrie"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "woonplaats": {
+                        "identificatie": "1152",
+                        "naam": "Nijmegen",
+                        "status": "Woonplaats aangewezen"
+                    },
+                    "_embedded": {
+                        "geometrie": { "type": "Point", "coordinates": [187000.0, 428000.0] }
+                    }
+                })))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let bag_client = BagClientBuilder::new("key").base_url(&uri).build();
+
+            let straat = bag_client
+                .get_openbareruimte("0268300000002333")
+                .await
+                .unwrap();
+            assert_eq!(straat.naam, "Castellastraat");
+            assert_eq!(straat.openbare_ruimte_type, "Weg");
+            assert_eq!(straat.korte_naam, None);
+
+            let woonplaats = bag_client
+                .get_woonplaats(&straat.woonplaats_identificatie)
+                .await
+                .unwrap();
+            assert_eq!(woonplaats.naam, "Nijmegen");
+            assert!(woonplaats.geometry.is_some());
+        });
+    }
+
+    #[test]
+    fn test_get_panden_in_bbox() {
+        use crate::util::coordinate_wgs84_to_rijksdriehoek;
+        use geo::Coord;
+
+        let bag_client = BagClientBuilder::new(&get_bag_key()).build();
+
+        // The TG office and its neighbours
+        let office = coordinate_wgs84_to_rijksdriehoek(5.86135, 51.83717);
+        let bbox = Rect::new(
+            office - Coord { x: 50.0, y: 50.0 },
+            office + Coord { x: 50.0, y: 50.0 },
+        );
+
+        let panden = aw!(bag_client.get_panden_in_bbox(bbox)).unwrap();
+        assert!(panden
+            .iter()
+            .any(|pand| pand.identificatie == "0268100000317781"));
+    }
+
+    #[test]
+    fn test_get_panden_in_bbox_tiles() {
+        use geo::Coord;
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(matchers::path("/panden"))
+                .and(matchers::header("Content-Crs", "epsg:28992"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "_embedded": { "panden": [{ "pand": {
+                        "identificatie": "0268100000317781",
+                        "geometrie": { "type": "Point", "coordinates": [187000.0, 428000.0] },
+                        "oorspronkelijkBouwjaar": "1925",
+                        "status": "Pand in gebruik"
+                    }}]}
+                })))
+                // 1 km² is split into 2 by 2 tiles
+                .expect(4)
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let bag_client = BagClientBuilder::new("key").base_url(&uri).build();
+
+            let bbox = Rect::new(
+                Coord {
+                    x: 187000.0,
+                    y: 428000.0,
+                },
+                Coord {
+                    x: 188000.0,
+                    y: 429000.0,
+                },
+            );
+            let panden = bag_client.get_panden_in_bbox(bbox).await.unwrap();
+
+            assert_eq!(panden.len(), 1);
+            assert_eq!(panden[0].bouwjaar, "1925");
+        });
+    }
+
+    #[test]
+    fn test_get_primary_pand() {
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        let pand = |id: &str, size: f64| {
+            serde_json::json!({
+                "pand": {
+                    "identificatie": id,
+                    "geometrie": {
+                        "type": "Polygon",
+                        "coordinates": [[[0.0, 0.0], [size, 0.0], [size, size], [0.0, size], [0.0, 0.0]]]
+                    },
+                    "oorspronkelijkBouwjaar": "1900",
+                    "status": "Pand in gebruik"
+                }
+            })
+        };
+
+        aw!(async {
+            let server = MockServer::start().await;
+            let uri = server.uri();
+
+            let links: Vec<_> = ["small", "large", "medium"]
+                .iter()
+                .map(|id| serde_json::json!({ "href": format!("{}/panden/{}", uri, id) }))
+                .collect();
+            Mock::given(matchers::path("/verblijfsobjecten/0268010000084126"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "verblijfsobject": { "status": "Verblijfsobject in gebruik", "gebruiksdoelen": [] },
+                    "_links": { "maaktDeelUitVan": links }
+                })))
+                .mount(&server)
+                .await;
+            for (id, size) in [("small", 5.0), ("large", 20.0), ("medium", 10.0)] {
+                Mock::given(matchers::path(format!("/panden/{}", id)))
+                    .respond_with(ResponseTemplate::new(200).set_body_json(pand(id, size)))
+                    .mount(&server)
+                    .await;
+            }
+
+            let bag_client = BagClientBuilder::new("key").base_url(&uri).build();
+
+            let primary = bag_client.get_primary_pand("0268010000084126").await;
+            assert_eq!(primary.unwrap().unwrap().identificatiecode, "large");
+        });
+    }
+
+    #[test]
+    fn test_get_unique_panden_progress() {
+        use std::sync::Mutex;
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            let uri = server.uri();
+
+            Mock::given(matchers::path_regex("^/verblijfsobjecten/"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "verblijfsobject": { "gebruiksdoelen": [] },
+                    "_links": { "maaktDeelUitVan": [{ "href": format!("{uri}/panden/1") }] }
+                })))
+                .mount(&server)
+                .await;
+            Mock::given(matchers::path("/panden/1"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "pand": {
+                        "identificatie": "1",
+                        "geometrie": {
+                            "type": "Polygon",
+                            "coordinates": [[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 0.0]]]
+                        },
+                        "oorspronkelijkBouwjaar": "1900",
+                        "status": "Pand in gebruik"
+                    }
+                })))
+                .mount(&server)
+                .await;
+
+            let bag_client = BagClientBuilder::new("key").base_url(&uri).build();
+
+            let calls = Mutex::new(Vec::new());
+            let panden = bag_client
+                .get_unique_panden_with_progress(&["a", "b", "c"], |done, total| {
+                    calls.lock().unwrap().push((done, total))
+                })
+                .await
+                .unwrap();
+
+            assert_eq!(panden.len(), 1);
+            assert_eq!(calls.into_inner().unwrap(), [(1, 3), (2, 3), (3, 3)]);
+        });
+    }
+
+    #[test]
+    fn test_building_footprint() {
+        use geo::algorithm::area::Area;
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        let pand = |id: &str, min_x: f64, max_x: f64| {
+            serde_json::json!({
+                "pand": {
+                    "identificatie": id,
+                    "geometrie": {
+                        "type": "Polygon",
+                        "coordinates": [[[min_x, 0.0], [max_x, 0.0], [max_x, 10.0], [min_x, 10.0], [min_x, 0.0]]]
+                    },
+                    "oorspronkelijkBouwjaar": "1900",
+                    "status": "Pand in gebruik"
+                }
+            })
+        };
+
+        aw!(async {
+            let server = MockServer::start().await;
+            let uri = server.uri();
+
+            let links: Vec<_> = ["house", "shed"]
+                .iter()
+                .map(|id| serde_json::json!({ "href": format!("{}/panden/{}", uri, id) }))
+                .collect();
+            Mock::given(matchers::path("/verblijfsobjecten/0268010000084126"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "verblijfsobject": { "status": "Verblijfsobject in gebruik", "gebruiksdoelen": [] },
+                    "_links": { "maaktDeelUitVan": links }
+                })))
+                .mount(&server)
+                .await;
+            Mock::given(matchers::path("/verblijfsobjecten/0268010000000000"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "verblijfsobject": { "gebruiksdoelen": [] },
+                    "_links": { "maaktDeelUitVan": [] }
+                })))
+                .mount(&server)
+                .await;
+            for (id, min_x, max_x) in [("house", 0.0, 12.0), ("shed", 12.0, 15.0)] {
+                Mock::given(matchers::path(format!("/panden/{}", id)))
+                    .respond_with(ResponseTemplate::new(200).set_body_json(pand(id, min_x, max_x)))
+                    .mount(&server)
+                    .await;
+            }
+
+            let bag_client = BagClientBuilder::new("key").base_url(&uri).build();
+
+            let footprint = bag_client
+                .building_footprint("0268010000084126")
+                .await
+                .unwrap()
+                .unwrap();
+            let pandvlak = bag_client.total_pandvlak("0268010000084126").await.unwrap();
+
+            assert_eq!(footprint.0.len(), 1);
+            assert!((footprint.unsigned_area() - pandvlak).abs() < 1.0);
+
+            let none = bag_client.building_footprint("0268010000000000").await;
+            assert_eq!(none.unwrap(), None);
+        });
+    }
+
+    #[test]
+    fn test_coordinate_space_serde() {
+        let json = serde_json::to_string(&BagCoordinateSpace::Rijksdriehoek).unwrap();
+        assert_eq!(json, "\"epsg:28992\"");
+
+        let space: BagCoordinateSpace = serde_json::from_str(&json).unwrap();
+        assert_eq!(space, BagCoordinateSpace::Rijksdriehoek);
+    }
+
+    #[test]
+    fn test_pand_centroid() {
+        use geo::algorithm::contains::Contains;
+
+        let bag_client = BagClientBuilder::new(&get_bag_key()).build();
+
+        let object_id = "0268010000084126";
+        let buildings = aw!(bag_client.get_panden(object_id)).unwrap();
+        let pand = buildings.first().unwrap();
+
+        let polygon = geojson_value_to_polygon(&pand.geometry.value).unwrap();
+        assert!(polygon.contains(&pand.centroid().unwrap()));
+    }
+
+    #[test]
+    fn test_pand_hash() {
+        use std::collections::HashSet;
+
+        let geometry = Geometry::new(geojson::Value::Point(vec![187_000.0, 428_000.0]));
+        let pand = Pand::from_parts("0268100000317781", geometry);
+        let mut other = pand.clone();
+        other.bouwjaar = "1925".to_string();
+
+        let panden: HashSet<Pand> = [pand, other].into_iter().collect();
+        assert_eq!(panden.len(), 1);
+    }
+
+    #[test]
+    fn test_pand_to_feature() {
+        let geometry = Geometry::new(geojson::Value::Point(vec![187_000.0, 428_000.0]));
+        let mut pand = Pand::from_parts("0268100000317781", geometry.clone());
+        pand.bouwjaar = "1925".to_string();
+
+        let feature = pand.to_feature();
+        let properties = feature.properties.unwrap();
+
+        assert_eq!(feature.geometry, Some(geometry));
+        assert_eq!(properties["identificatiecode"], "0268100000317781");
+        assert_eq!(properties["bouwjaar"], "1925");
+        assert!(!properties.contains_key("geometry"));
+    }
+
+    #[test]
+    fn test_pand_to_wkt() {
+        use crate::geometry::{geojson_geometry_to_geo, wkt_to_geo};
+
+        let geometry = Geometry::new(geojson::Value::MultiPolygon(vec![
+            vec![vec![
+                vec![187_000.0, 428_000.0],
+                vec![187_010.0, 428_000.0],
+                vec![187_010.0, 428_010.0],
+                vec![187_000.0, 428_000.0],
+            ]],
+            vec![vec![
+                vec![187_020.0, 428_000.0],
+                vec![187_030.0, 428_000.0],
+                vec![187_030.0, 428_010.0],
+                vec![187_020.0, 428_000.0],
+            ]],
+        ]));
+        let pand = Pand::from_parts("0268100000317781", geometry);
+
+        let wkt = pand.to_wkt().unwrap();
+
+        assert!(wkt.starts_with("MULTIPOLYGON"));
+        assert_eq!(
+            wkt_to_geo(&wkt).unwrap(),
+            geojson_geometry_to_geo(&pand.geometry).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_pand_statuses() {
+        let bag_client = BagClientBuilder::new(&get_bag_key()).build();
+
+        let statuses = aw!(bag_client.pand_statuses("0268010000084126")).unwrap();
+        assert_eq!(statuses, BTreeSet::from([PandStatus::InGebruik]));
+    }
+
+    #[test]
+    fn test_get_unique_panden() {
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            let uri = server.uri();
+
+            // Two distinct objects in pand 1, and one object in pand 2
+            for (object_id, pand_id) in [("a1", "1"), ("a2", "1"), ("b", "2")] {
+                Mock::given(matchers::path(format!("/verblijfsobjecten/{object_id}")))
+                    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                        "verblijfsobject": { "gebruiksdoelen": [] },
+                        "_links": {
+                            "maaktDeelUitVan": [{ "href": format!("{uri}/panden/{pand_id}") }]
+                        }
+                    })))
+                    .mount(&server)
+                    .await;
+            }
+            for pand_id in ["1", "2"] {
+                Mock::given(matchers::path(format!("/panden/{pand_id}")))
+                    .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                        "pand": {
+                            "identificatie": pand_id,
+                            "geometrie": {
+                                "type": "Polygon",
+                                "coordinates": [[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 0.0]]]
+                            },
+                            "oorspronkelijkBouwjaar": "1900",
+                            "status": "Pand in gebruik"
+                        }
+                    })))
+                    .mount(&server)
+                    .await;
+            }
+
+            let bag_client = BagClientBuilder::new("key").base_url(&uri).build();
+
+            let same = bag_client.get_unique_panden(&["a1", "a2"]).await.unwrap();
+            assert_eq!(same.len(), 1);
+
+            let different = bag_client
+                .get_unique_panden(&["a1", "b", "a2"])
+                .await
+                .unwrap();
+            let ids: Vec<_> = different
+                .iter()
+                .map(|p| p.identificatiecode.as_str())
+                .collect();
+            assert_eq!(ids, ["1", "2"]);
+        });
+    }
+
+    #[test]
+    fn test_total_pandvlak() {
+        let bag_client = BagClientBuilder::new(&get_bag_key()).build();
+
+        let object_id = "0268010000084126";
+        let total = aw!(bag_client.total_pandvlak(object_id)).unwrap();
+
+        assert!(total > 0.0);
+    }
 }
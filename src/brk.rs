@@ -5,32 +5,105 @@
 //! for more information on its capabilities.
 use std::cmp::Ordering;
 
+mod features;
+pub mod wfs;
+
 pub use crate::CoordinateSpace;
-use crate::Error;
+use crate::{
+    brk::features::FeatureSplitter,
+    geometry::{geojson_geometry_to_geo, geojson_value_to_multipolygon},
+    transport::{self, BodyChunks, Transport},
+    util::validate_geojson_polygon,
+    Error,
+};
 
-use geojson::{FeatureCollection, Geometry};
-use reqwest::Client;
+use chrono::NaiveDate;
+use futures::Stream;
+use geo::{Coord, Point, Rect};
+use geojson::{Feature, FeatureCollection, Geometry};
+use reqwest::header::HeaderMap;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
+use tokio::sync::OnceCell;
 
+/// Client for the kadastrale kaart WFS.
+///
+/// Clones share the connection pool (and response cache), so there is no need to wrap
+/// the client in an `Arc` to use it from several tasks.
+#[derive(Clone)]
 pub struct BrkClient {
-    client: Client,
+    client: Transport,
+    base_url: String,
+    accept_crs: CoordinateSpace,
+    peildatum: Option<NaiveDate>,
+    page_size: usize,
+    max_retries: u32,
+    validate_geometry: bool,
+    active_only: bool,
+    wfs_version: String,
+    typename: String,
+    /// The CRS supported by the feature type, fetched on first use
+    supported_crs: Arc<OnceCell<Vec<String>>>,
 }
 
 pub struct BrkClientBuilder<'a> {
     accept_crs: CoordinateSpace,
+    peildatum: Option<NaiveDate>,
+    page_size: usize,
+    max_retries: u32,
+    validate_geometry: bool,
+    active_only: bool,
+    chunked_transfer: bool,
+    wfs_version: &'a str,
+    typename: &'a str,
     connection_timeout_secs: u64,
     request_timeout_secs: u64,
+    follow_redirects: bool,
+    max_concurrency: usize,
+    rate_limit_retries: u32,
+    max_retry_after: Duration,
     user_agent: &'a str,
+    user_agent_suffix: bool,
+    base_url: &'a str,
+    headers: HeaderMap,
+    #[cfg(feature = "cache")]
+    cache_capacity: usize,
+    #[cfg(feature = "cache")]
+    cache_ttl: Option<Duration>,
+}
+
+impl<'a> Default for BrkClientBuilder<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'a> BrkClientBuilder<'a> {
-    pub fn new(user_agent: &'a str) -> Self {
+    pub fn new() -> Self {
         Self {
-            user_agent,
+            user_agent: crate::DEFAULT_USER_AGENT,
+            user_agent_suffix: true,
+            base_url: BrkClient::BRK_URL,
+            headers: HeaderMap::new(),
+            #[cfg(feature = "cache")]
+            cache_capacity: 0,
+            #[cfg(feature = "cache")]
+            cache_ttl: None,
             accept_crs: CoordinateSpace::Gps,
+            peildatum: None,
+            page_size: 1000,
+            max_retries: 3,
+            validate_geometry: false,
+            active_only: false,
+            chunked_transfer: false,
+            wfs_version: BrkClient::WFS_VERSION,
+            typename: BrkClient::PERCEEL_TYPENAME,
             connection_timeout_secs: 5,
             request_timeout_secs: 20,
+            follow_redirects: true,
+            max_concurrency: 0,
+            rate_limit_retries: 0,
+            max_retry_after: Duration::from_secs(60),
         }
     }
 
@@ -38,6 +111,71 @@ impl<'a> BrkClientBuilder<'a> {
         self.accept_crs = accept_crs;
         self
     }
+
+    /// Only return lots that were already valid (`beginGeldigheid`) on the given reference date.
+    ///
+    /// Note that the kadastrale kaart only serves the current state of each lot, so lots
+    /// that have since been split or merged cannot be reconstructed this way.
+    pub fn peildatum(&mut self, peildatum: NaiveDate) -> &mut Self {
+        self.peildatum = Some(peildatum);
+        self
+    }
+
+    /// The number of lots to request per page when streaming.
+    /// A page size of zero is raised to one.
+    pub fn page_size(&mut self, page_size: usize) -> &mut Self {
+        self.page_size = page_size.max(1);
+        self
+    }
+
+    /// How often [`BrkClient::download_lots`] retries a page after the connection dropped
+    /// or the server failed, resuming after the last received lot (3 by default).
+    pub fn max_retries(&mut self, max_retries: u32) -> &mut Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    /// Check the geometry of every lot with [`crate::util::validate_geojson_polygon`],
+    /// failing with [`Error::InvalidGeometry`] instead of returning a broken lot.
+    /// Off by default.
+    pub fn validate_geometry(&mut self, validate_geometry: bool) -> &mut Self {
+        self.validate_geometry = validate_geometry;
+        self
+    }
+
+    /// Leave out historical lots, whose registration has ended (off by default).
+    ///
+    /// A lot that is split or merged gets an `eindRegistratie`, while the resulting lots are
+    /// registered anew. The kadastrale kaart normally only serves the current lots, so this
+    /// only drops lots for which the service returns an `eindRegistratie`, see
+    /// [`Lot::is_active`].
+    pub fn active_only(&mut self, active_only: bool) -> &mut Self {
+        self.active_only = active_only;
+        self
+    }
+
+    /// Send a `transfer-encoding: chunked` header with every request (off by default),
+    /// see [`crate::bag::BagClientBuilder::chunked_transfer`].
+    pub fn chunked_transfer(&mut self, chunked_transfer: bool) -> &mut Self {
+        self.chunked_transfer = chunked_transfer;
+        self
+    }
+
+    /// The WFS version to request (`2.0.0` by default).
+    pub fn wfs_version(&mut self, wfs_version: &'a str) -> &mut Self {
+        self.wfs_version = wfs_version;
+        self
+    }
+
+    /// The feature type to fetch lots from (`kadastralekaartv5:perceel` by default),
+    /// e.g. when PDOK publishes a new version of the kadastrale kaart.
+    ///
+    /// The lots are parsed using the property names of the default feature type, so a
+    /// feature type with different property names will yield lots with missing fields.
+    pub fn typename(&mut self, typename: &'a str) -> &mut Self {
+        self.typename = typename;
+        self
+    }
 }
 
 impl<'a> crate::ClientBuilder<'a> for BrkClientBuilder<'a> {
@@ -53,8 +191,61 @@ impl<'a> crate::ClientBuilder<'a> for BrkClientBuilder<'a> {
         self
     }
 
+    fn follow_redirects(&mut self, follow_redirects: bool) -> &mut Self {
+        self.follow_redirects = follow_redirects;
+        self
+    }
+
+    fn max_concurrency(&mut self, max_concurrency: usize) -> &mut Self {
+        self.max_concurrency = max_concurrency;
+        self
+    }
+
+    fn rate_limit_retries(&mut self, rate_limit_retries: u32) -> &mut Self {
+        self.rate_limit_retries = rate_limit_retries;
+        self
+    }
+
+    fn max_retry_after(&mut self, max_retry_after: Duration) -> &mut Self {
+        self.max_retry_after = max_retry_after;
+        self
+    }
+
+    fn user_agent(&mut self, user_agent: &'a str) -> &mut Self {
+        self.user_agent = user_agent;
+        self
+    }
+
+    fn user_agent_suffix(&mut self, user_agent_suffix: bool) -> &mut Self {
+        self.user_agent_suffix = user_agent_suffix;
+        self
+    }
+
+    fn header(&mut self, name: &str, value: &str) -> Result<&mut Self, Error> {
+        let (name, value) = crate::parse_header(name, value)?;
+        self.headers.insert(name, value);
+        Ok(self)
+    }
+
+    fn base_url(&mut self, base_url: &'a str) -> &mut Self {
+        self.base_url = base_url;
+        self
+    }
+
+    #[cfg(feature = "cache")]
+    fn cache_capacity(&mut self, capacity: usize) -> &mut Self {
+        self.cache_capacity = capacity;
+        self
+    }
+
+    #[cfg(feature = "cache")]
+    fn cache_ttl(&mut self, ttl: Duration) -> &mut Self {
+        self.cache_ttl = Some(ttl);
+        self
+    }
+
     fn build(&self) -> BrkClient {
-        use reqwest::header::{HeaderMap, HeaderValue};
+        use reqwest::header::HeaderValue;
 
         let mut headers = HeaderMap::new();
 
@@ -64,25 +255,50 @@ impl<'a> crate::ClientBuilder<'a> for BrkClientBuilder<'a> {
             HeaderValue::from_static(self.accept_crs.as_str()),
         );
 
-        headers.insert(
-            "transfer-encoding",
-            HeaderValue::from_str("chunked").unwrap(),
+        if self.chunked_transfer {
+            headers.insert("transfer-encoding", HeaderValue::from_static("chunked"));
+        }
+
+        headers.extend(self.headers.clone());
+
+        let client_builder = transport::client_builder(
+            self.user_agent,
+            self.user_agent_suffix,
+            headers,
+            self.connection_timeout_secs,
+            self.request_timeout_secs,
+            self.follow_redirects,
         );
 
-        let client = reqwest::ClientBuilder::new()
-            .user_agent(self.user_agent)
-            .default_headers(headers)
-            .connect_timeout(Duration::from_secs(self.connection_timeout_secs))
-            .timeout(Duration::new(self.request_timeout_secs, 0))
-            .build()
-            .unwrap();
+        let client = Transport::new(client_builder.build().unwrap())
+            .with_max_concurrency(std::num::NonZeroUsize::new(self.max_concurrency))
+            .with_rate_limit_retries(self.rate_limit_retries, self.max_retry_after);
 
-        BrkClient { client }
+        #[cfg(feature = "cache")]
+        let client = client.with_cache(self.cache_capacity, self.cache_ttl);
+
+        BrkClient {
+            client,
+            base_url: self.base_url.to_string(),
+            accept_crs: self.accept_crs,
+            peildatum: self.peildatum,
+            page_size: self.page_size,
+            max_retries: self.max_retries,
+            validate_geometry: self.validate_geometry,
+            active_only: self.active_only,
+            wfs_version: self.wfs_version.to_string(),
+            typename: self.typename.to_string(),
+            supported_crs: Arc::default(),
+        }
     }
 }
 
 impl BrkClient {
     const BRK_URL: &'static str = "https://service.pdok.nl/kadaster/kadastralekaart/wfs/v5_0";
+    const PERCEEL_TYPENAME: &'static str = "kadastralekaartv5:perceel";
+    const WFS_VERSION: &'static str = "2.0.0";
+    /// The number of lots [`BrkClient::get_lots`] fetches at a time
+    const GET_LOTS_CONCURRENCY: usize = 4;
 
     /// Fetch a singular lot according to its uid,
     /// which is comprised of gemeentecode, sectie and perceelnummer.
@@ -92,150 +308,1997 @@ impl BrkClient {
         sectie: &str,
         perceelnummer: &str,
     ) -> Result<Vec<Lot>, Error> {
-        // Filters lot by gemeentecode, sectie and perceelnummer
-        let filter = format!(
-            r#"
-<Filter>
-  <And>
-    <And>
-      <PropertyIsEqualTo>
-        <PropertyName>sectie</PropertyName>
-        <Literal>{sectie}</Literal>
-      </PropertyIsEqualTo>
-      <PropertyIsEqualTo>
-        <PropertyName>perceelnummer</PropertyName>
-        <Literal>{perceelnummer}</Literal>
-      </PropertyIsEqualTo>
-    </And>
-    <PropertyIsEqualTo>
-      <PropertyName>AKRKadastraleGemeenteCodeWaarde</PropertyName>
-      <Literal>{gemeentecode}</Literal>
-    </PropertyIsEqualTo>
-  </And>
-</Filter>"#
-        );
+        let filter = self.lot_filter(gemeentecode, sectie, perceelnummer);
 
-        let u = url::Url::parse_with_params(
-            &format!("{}", BrkClient::BRK_URL),
-            &[
-                ("request", "GetFeature"),
-                ("service", "WFS"),
-                ("version", "2.0.0"),
-                ("typenames", "kadastralekaartv5:perceel"),
-                ("outputFormat", "application/json"),
-                ("filter", &filter),
-            ],
-        )
-            .unwrap();
-        
-        let client_response = self
-            .client
-            .get(u.as_str())
-            .send()
+        self.get_lots_filtered(self.perceel_query(&filter)).await
+    }
+
+    /// Fetch several lots like [`BrkClient::get_lot`], given as (gemeentecode, sectie,
+    /// perceelnummer), e.g. the percelen listed in a document.
+    ///
+    /// A few lots are fetched at a time. The results are in the order of the keys, and a
+    /// lot that fails does not affect the others.
+    pub async fn get_lots(
+        &self,
+        keys: &[(String, String, String)],
+    ) -> Vec<Result<Vec<Lot>, Error>> {
+        use futures::StreamExt;
+
+        futures::stream::iter(keys)
+            .map(|(gemeentecode, sectie, perceelnummer)| {
+                self.get_lot(gemeentecode, sectie, perceelnummer)
+            })
+            .buffered(Self::GET_LOTS_CONCURRENCY)
+            .collect()
             .await
-            .map_err(|e| Error::NetworkProblem(e))?;
+    }
 
-        let json: FeatureCollection = client_response.json().await.map_err(|e| Error::JsonProblem(e))?;
-        let lots: Vec<Lot> = json
-            .features
-            .iter()
-            .filter_map(|feature| {
-                Some(Lot {
-                    id: feature.property("identificatieLokaalID")?.as_str()?.to_string(),
-                    gemeentenaam: Some(
-                        feature.property("kadastraleGemeenteWaarde")?.as_str()?.to_string()
-                    ),
-                    kadastralegemeentecode: Some(
-                        feature.property("AKRKadastraleGemeenteCodeWaarde")?.as_str()?.to_string(),
-                    ),
-                    grootte: feature.property("kadastraleGrootteWaarde")?.as_f64(),
-                    sectie: Some(feature.property("sectie")?.as_str()?.to_string()),
-                    perceelnummer: Some(
-                        feature.property("perceelnummer")?.as_u64()?,
-                    ),
-                    geometry: feature.geometry.clone()?,
+    /// Fetch a lot by its [`Lot::id`] (`identificatieLokaalID`), e.g. to refresh a lot that
+    /// was stored earlier. Yields `None` when no lot has the id (at the peildatum, if any).
+    pub async fn get_lot_by_id(&self, id: &str) -> Result<Option<Lot>, Error> {
+        let mut conditions = vec![wfs::Filter::property_eq("identificatieLokaalID", id)];
+
+        if let Some(peildatum) = self.peildatum {
+            conditions.push(wfs::Filter::property_le("beginGeldigheid", peildatum));
+        }
+
+        let filter = wfs::Filter::and(conditions).into();
+
+        match self.get_lots_filtered(self.perceel_query(&filter)).await {
+            Ok(lots) => Ok(lots.into_iter().next()),
+            Err(Error::EmptyResponse) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Fetch a singular lot like [`BrkClient::get_lot`], with its geometry in the given
+    /// coordinate space instead of the one configured on the builder.
+    pub async fn get_lot_with_crs(
+        &self,
+        gemeentecode: &str,
+        sectie: &str,
+        perceelnummer: &str,
+        space: CoordinateSpace,
+    ) -> Result<Vec<Lot>, Error> {
+        let filter = self.lot_filter(gemeentecode, sectie, perceelnummer);
+        let query = FeatureQuery {
+            crs: Some(space),
+            ..self.perceel_query(&filter)
+        };
+
+        self.get_lots_filtered(query).await
+    }
+
+    /// Fetch a singular lot like [`BrkClient::get_lot`], failing when the request takes
+    /// longer than `timeout` instead of the configured request timeout.
+    ///
+    /// Like all requests, it can also be cancelled by dropping the returned future.
+    pub async fn get_lot_with_timeout(
+        &self,
+        gemeentecode: &str,
+        sectie: &str,
+        perceelnummer: &str,
+        timeout: Duration,
+    ) -> Result<Vec<Lot>, Error> {
+        let filter = self.lot_filter(gemeentecode, sectie, perceelnummer);
+        let query = FeatureQuery {
+            timeout: Some(timeout),
+            ..self.perceel_query(&filter)
+        };
+
+        self.get_lots_filtered(query).await
+    }
+
+    /// Fetch a singular lot like [`BrkClient::get_lot`], returning the response body
+    /// in the given format as is, e.g. for GIS pipelines that ingest GML directly.
+    pub async fn get_lot_raw(
+        &self,
+        gemeentecode: &str,
+        sectie: &str,
+        perceelnummer: &str,
+        format: WfsOutputFormat,
+    ) -> Result<String, Error> {
+        let filter = self.lot_filter(gemeentecode, sectie, perceelnummer);
+        let query = FeatureQuery {
+            output_format: format,
+            ..self.perceel_query(&filter)
+        };
+
+        let client_response = self.send_feature_query(&query).await?;
+
+        Ok(client_response.text().await?)
+    }
+
+    /// Filter a lot by its uid, restricted to the peildatum if one is configured
+    fn lot_filter(&self, gemeentecode: &str, sectie: &str, perceelnummer: &str) -> WfsFilter {
+        let mut conditions = vec![
+            wfs::Filter::property_eq("sectie", sectie),
+            wfs::Filter::property_eq("perceelnummer", perceelnummer),
+            wfs::Filter::property_eq("AKRKadastraleGemeenteCodeWaarde", gemeentecode),
+        ];
+
+        // Only lots that already existed at the peildatum, if any
+        if let Some(peildatum) = self.peildatum {
+            conditions.push(wfs::Filter::property_le("beginGeldigheid", peildatum));
+        }
+
+        wfs::Filter::and(conditions).into()
+    }
+
+    /// Fetch all lots matching the given CQL expression,
+    /// e.g. `kadastraleGrootteWaarde > 1000`.
+    pub async fn get_lots_cql(&self, cql: &str) -> Result<Vec<Lot>, Error> {
+        let cql = match self.peildatum {
+            Some(peildatum) => format!("({cql}) AND beginGeldigheid <= '{peildatum}'"),
+            None => cql.to_string(),
+        };
+
+        self.get_lots_filtered(self.perceel_query(&WfsFilter::Cql(cql)))
+            .await
+    }
+
+    /// Fetch the lots that share a boundary with the given lot.
+    ///
+    /// Candidates are fetched using a slightly enlarged bounding box around the lot,
+    /// of which only those within a small distance of the lot's geometry are kept.
+    pub async fn get_neighbors(&self, lot: &Lot) -> Result<Vec<Lot>, Error> {
+        use geo::algorithm::{bounding_rect::BoundingRect, euclidean_distance::EuclideanDistance};
+
+        // A lot without a polygon has no neighbors
+        let polygons = match lot_polygons(lot) {
+            Some(polygons) => polygons,
+            None => return Ok(vec![]),
+        };
+
+        let tolerance = match self.accept_crs {
+            CoordinateSpace::Rijksdriehoek => 0.5,
+            CoordinateSpace::Gps => 0.00001,
+        };
+
+        let bbox = match geo::MultiPolygon::new(polygons.clone()).bounding_rect() {
+            Some(bbox) => crate::util::add_margin(bbox, tolerance),
+            None => return Ok(vec![]),
+        };
+
+        let filter = WfsFilter::Bbox(bbox);
+        let candidates = match self.get_lots_filtered(self.perceel_query(&filter)).await {
+            Ok(candidates) => candidates,
+            Err(Error::EmptyResponse) => vec![],
+            Err(e) => return Err(e),
+        };
+
+        let neighbors = candidates
+            .into_iter()
+            .filter(|candidate| candidate.id != lot.id)
+            .filter(|candidate| {
+                lot_polygons(candidate).is_some_and(|others| {
+                    polygons.iter().any(|polygon| {
+                        others
+                            .iter()
+                            .any(|other| polygon.euclidean_distance(other) <= tolerance)
+                    })
                 })
             })
             .collect();
 
+        Ok(neighbors)
+    }
+
+    /// Lazily page through all lots matching the given filter.
+    ///
+    /// The next page is only requested once the lots of the current page have been consumed,
+    /// so `.take(n)` on the stream does not fetch more pages than necessary.
+    pub fn lots_stream(&self, filter: WfsFilter) -> impl Stream<Item = Result<Lot, Error>> + '_ {
+        struct State {
+            filter: WfsFilter,
+            start_index: usize,
+            page: std::vec::IntoIter<Lot>,
+            exhausted: bool,
+        }
+
+        let state = State {
+            filter,
+            start_index: 0,
+            page: Vec::new().into_iter(),
+            exhausted: false,
+        };
+
+        futures::stream::unfold(state, move |mut state| async move {
+            loop {
+                if let Some(lot) = state.page.next() {
+                    return Some((self.check_geometry(lot), state));
+                }
+
+                if state.exhausted {
+                    return None;
+                }
+
+                let query = FeatureQuery {
+                    start_index: Some(state.start_index),
+                    ..self.perceel_query(&state.filter)
+                };
+                let page = self.get_feature_page(&query).await;
+
+                match page {
+                    Ok(collection) => {
+                        let returned = collection.features.len();
+                        state.start_index += returned;
+                        state.exhausted = returned == 0 || returned < self.page_size;
+                        let lots = decode_lots(collection, self.response_crs(&query));
+                        state.page = self.filter_lots(lots).into_iter();
+                    }
+                    Err(e) => {
+                        state.exhausted = true;
+                        return Some((Err(e), state));
+                    }
+                }
+            }
+        })
+    }
+
+    /// Download all lots matching the given filter, e.g. all lots of a municipality.
+    ///
+    /// Like [`BrkClient::lots_stream`] the lots are requested in pages, but every page is
+    /// decoded while it is received, so only a single feature is held in memory at a time.
+    /// When the connection drops or the server fails, the download resumes at the lot
+    /// after the last one that was received, up to [`BrkClientBuilder::max_retries`] times
+    /// in a row.
+    pub fn download_lots(&self, filter: WfsFilter) -> impl Stream<Item = Result<Lot, Error>> + '_ {
+        use std::collections::VecDeque;
+
+        struct State {
+            filter: WfsFilter,
+            start_index: usize,
+            body: Option<BodyChunks>,
+            splitter: FeatureSplitter,
+            /// The number of features received in the current page
+            received: usize,
+            ready: VecDeque<Lot>,
+            retries_left: u32,
+            exhausted: bool,
+        }
+
+        let state = State {
+            filter,
+            start_index: 0,
+            body: None,
+            splitter: FeatureSplitter::new(),
+            received: 0,
+            ready: VecDeque::new(),
+            retries_left: self.max_retries,
+            exhausted: false,
+        };
+
+        futures::stream::unfold(state, move |mut state| async move {
+            loop {
+                if let Some(lot) = state.ready.pop_front() {
+                    return Some((self.check_geometry(lot), state));
+                }
+
+                let error = match &mut state.body {
+                    Some(body) => match body.next().await {
+                        Ok(Some(chunk)) => {
+                            for feature in state.splitter.push(chunk.as_ref()) {
+                                state.received += 1;
+                                state.start_index += 1;
+                                match serde_json::from_slice(&feature) {
+                                    Ok(feature) => state.ready.extend(
+                                        lot_from_feature(&feature, self.accept_crs)
+                                            .filter(|lot| !self.active_only || lot.is_active()),
+                                    ),
+                                    Err(e) => log::warn!("dropped lot feature: {}", e),
+                                }
+                            }
+                            continue;
+                        }
+                        Ok(None) => {
+                            state.body = None;
+                            state.exhausted =
+                                state.received == 0 || state.received < self.page_size;
+                            state.retries_left = self.max_retries;
+                            continue;
+                        }
+                        Err(e) => Error::from(e),
+                    },
+                    None if state.exhausted => return None,
+                    None => {
+                        let query = FeatureQuery {
+                            start_index: Some(state.start_index),
+                            ..self.perceel_query(&state.filter)
+                        };
+
+                        match self.send_feature_query(&query).await {
+                            Ok(response) => {
+                                state.body = Some(BodyChunks::new(response));
+                                state.splitter = FeatureSplitter::new();
+                                state.received = 0;
+                                continue;
+                            }
+                            Err(e) => e,
+                        }
+                    }
+                };
+
+                state.body = None;
+
+                if state.retries_left > 0 && is_retryable(&error) {
+                    state.retries_left -= 1;
+                    log::warn!(
+                        "retrying lot download at index {}: {:?}",
+                        state.start_index,
+                        error
+                    );
+                } else {
+                    state.exhausted = true;
+                    return Some((Err(error), state));
+                }
+            }
+        })
+    }
+
+    /// Fetch all lots matching the given filter, in a single request.
+    async fn get_lots_filtered(&self, query: FeatureQuery<'_>) -> Result<Vec<Lot>, Error> {
+        let collection = self.get_feature_page(&query).await?;
+        let lots = self.filter_lots(decode_lots(collection, self.response_crs(&query)));
+
         if lots.is_empty() {
             Err(Error::EmptyResponse)
         } else {
-            Ok(lots)
+            lots.into_iter()
+                .map(|lot| self.check_geometry(lot))
+                .collect()
         }
     }
 
+    /// Leave out historical lots, if enabled on the builder
+    fn filter_lots(&self, mut lots: Vec<Lot>) -> Vec<Lot> {
+        if self.active_only {
+            lots.retain(Lot::is_active);
+        }
+
+        lots
+    }
+
+    /// Validate the geometry of a lot, if enabled on the builder
+    fn check_geometry(&self, lot: Lot) -> Result<Lot, Error> {
+        if self.validate_geometry {
+            validate_geojson_polygon(&lot.geometry.value).map_err(|error| {
+                Error::InvalidGeometry {
+                    id: lot.id.clone(),
+                    error,
+                }
+            })?;
+        }
+
+        Ok(lot)
+    }
+
+    /// Fetch the raw features of any feature type (layer) offered by the WFS,
+    /// e.g. `kadastralekaartv5:kadastralegrens`.
     ///
-    /// Check if API is up by looking up the TG office
-    ///
-    pub async fn get_brk_status(&self) -> Result<Vec<Lot>, Error> {
-        self.get_lot("HTT02", "M", "5038").await
+    /// See [`BrkClient::get_capabilities`] for the available feature types.
+    pub async fn get_features(
+        &self,
+        typename: &str,
+        filter: WfsFilter,
+    ) -> Result<FeatureCollection, Error> {
+        self.get_feature_page(&FeatureQuery::new(typename, &filter))
+            .await
     }
-}
 
-/// A singular lot along with its geometry and size.
-#[derive(Deserialize, Serialize, Debug, Clone)]
-pub struct Lot {
-    pub id: String,
-    #[serde(rename = "kadastraleGemeentenaam")]
-    pub gemeentenaam: Option<String>,
-    #[serde(rename = "kadastraleGemeentecode")]
-    pub kadastralegemeentecode: Option<String>,
-    #[serde(rename = "kadastraleGrootte")]
-    pub grootte: Option<f64>,
-    pub sectie: Option<String>,
-    pub perceelnummer: Option<u64>,
-    pub geometry: Geometry,
-}
+    /// Query the lots of the configured feature type
+    fn perceel_query<'q>(&'q self, filter: &'q WfsFilter) -> FeatureQuery<'q> {
+        FeatureQuery::new(&self.typename, filter)
+    }
 
-impl PartialEq for Lot {
-    fn eq(&self, other: &Self) -> bool {
-        self.id == other.id
+    /// Perform a GetFeature request
+    async fn get_feature_page(&self, query: &FeatureQuery<'_>) -> Result<FeatureCollection, Error> {
+        self.client.send_json(self.feature_request(query)).await
     }
-}
 
-impl Eq for Lot {}
+    /// Send a GetFeature request, leaving the response body to the caller
+    async fn send_feature_query(
+        &self,
+        query: &FeatureQuery<'_>,
+    ) -> Result<reqwest::Response, Error> {
+        self.client.send(self.feature_request(query)).await
+    }
 
-impl PartialOrd for Lot {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+    /// The coordinate space the features of a query are returned in
+    fn response_crs(&self, query: &FeatureQuery<'_>) -> CoordinateSpace {
+        query.crs.unwrap_or(self.accept_crs)
     }
-}
 
-impl Ord for Lot {
-    fn cmp(&self, other: &Self) -> Ordering {
-        if self.eq(other) {
-            Ordering::Equal
-        } else if self.id < other.id {
-            Ordering::Less
-        } else {
-            Ordering::Greater
+    /// Build a GetFeature request
+    fn feature_request(&self, query: &FeatureQuery<'_>) -> reqwest::RequestBuilder {
+        let crs = self.response_crs(query);
+        let (filter_name, filter_value) = query.filter.as_param(crs);
+        let mut u = url::Url::parse_with_params(
+            &self.base_url,
+            &[
+                ("request", "GetFeature"),
+                ("service", "WFS"),
+                ("version", &self.wfs_version),
+                ("typenames", query.typename),
+                ("outputFormat", query.output_format.as_str()),
+                (filter_name, &filter_value),
+            ],
+        )
+        .unwrap();
+
+        if let Some(start_index) = query.start_index {
+            u.query_pairs_mut()
+                .append_pair("count", &self.page_size.to_string())
+                .append_pair("startIndex", &start_index.to_string());
+        }
+
+        if let Some(crs) = query.crs {
+            u.query_pairs_mut().append_pair("srsName", crs.srs_name());
+        }
+
+        let mut request = self.client.get(u.as_str());
+
+        if let Some(crs) = query.crs {
+            request = request.header("Accept-Crs", crs.as_str());
         }
+
+        if let Some(timeout) = query.timeout {
+            request = transport::with_timeout(request, timeout);
+        }
+
+        request
     }
-}
 
-#[cfg(test)]
-mod test {
+    /// List the feature types (layers) offered by the WFS, along with their supported CRS.
+    pub async fn get_capabilities(&self) -> Result<Vec<FeatureType>, Error> {
+        let u = url::Url::parse_with_params(
+            &self.base_url,
+            &[
+                ("request", "GetCapabilities"),
+                ("service", "WFS"),
+                ("version", &self.wfs_version),
+            ],
+        )
+        .unwrap();
 
-    use super::*;
-    use crate::ClientBuilder;
+        let client_response = self.client.send(self.client.get(u.as_str())).await?;
 
-    macro_rules! aw {
-        ($e:expr) => {
-            tokio_test::block_on($e)
-        };
+        let xml = client_response.text().await?;
+
+        parse_capabilities(&xml).map_err(Error::XmlProblem)
     }
 
-    const VERSION: &str = env!("CARGO_PKG_VERSION");
+    /// The CRS supported by the configured feature type, the default one first, e.g.
+    /// `urn:ogc:def:crs:EPSG::28992`. Empty if the WFS does not offer the feature type.
+    ///
+    /// The list is fetched with [`BrkClient::get_capabilities`] on first use, and kept for
+    /// the lifetime of the client and its clones.
+    pub async fn supported_crs(&self) -> Result<Vec<String>, Error> {
+        let supported_crs = self
+            .supported_crs
+            .get_or_try_init(|| async {
+                let feature_types = self.get_capabilities().await?;
 
-    #[test]
-    fn test_get_lot() {
-        let ua = format!("pdok-apis brk {}", VERSION);
-        let brk_client = BrkClientBuilder::new(&ua)
-            .accept_crs(CoordinateSpace::Rijksdriehoek)
-            .build();
+                let supported_crs = feature_types
+                    .into_iter()
+                    .find(|feature_type| feature_type.name == self.typename)
+                    .map(|feature_type| {
+                        feature_type
+                            .default_crs
+                            .into_iter()
+                            .chain(feature_type.other_crs)
+                            .collect()
+                    })
+                    .unwrap_or_default();
 
-        let result = aw!(brk_client.get_lot("HTT02", "M", "5038"));
-        assert_eq!(result.is_ok(), true);
+                Ok::<_, Error>(supported_crs)
+            })
+            .await?;
+
+        Ok(supported_crs.clone())
+    }
+
+    ///
+    /// Check if API is up by looking up the TG office
+    ///
+    pub async fn get_brk_status(&self) -> Result<Vec<Lot>, Error> {
+        self.get_lot("HTT02", "M", "5038").await
+    }
+
+    /// Check if the API is up by looking up the TG office, failing when it takes longer
+    /// than `timeout` regardless of the configured request timeout.
+    ///
+    /// The cache is bypassed. Yields `false` when the API responds with an error status,
+    /// and fails on network problems and timeouts.
+    pub async fn probe(&self, timeout: Duration) -> Result<bool, Error> {
+        let filter = self.lot_filter("HTT02", "M", "5038");
+        let query = FeatureQuery {
+            timeout: Some(timeout),
+            ..self.perceel_query(&filter)
+        };
+
+        let response = match self.client.probe(self.feature_request(&query)).await? {
+            Some(response) => response,
+            None => return Ok(false),
+        };
+        let collection: FeatureCollection = transport::decode_json(response).await?;
+
+        Ok(!collection.features.is_empty())
+    }
+}
+
+/// Whether a failed download may succeed when it is attempted again
+fn is_retryable(error: &Error) -> bool {
+    match error {
+        Error::NetworkProblem(_) | Error::Timeout(_) => true,
+        Error::HttpStatus { code, .. } => *code >= 500,
+        _ => false,
+    }
+}
+
+/// The parameters of a single GetFeature request
+struct FeatureQuery<'q> {
+    typename: &'q str,
+    filter: &'q WfsFilter,
+    /// Request a single page, starting at the given index
+    start_index: Option<usize>,
+    /// Request the features in this coordinate space, instead of the client default
+    crs: Option<CoordinateSpace>,
+    /// Override the request timeout of the client
+    timeout: Option<Duration>,
+    output_format: WfsOutputFormat,
+}
+
+impl<'q> FeatureQuery<'q> {
+    fn new(typename: &'q str, filter: &'q WfsFilter) -> Self {
+        FeatureQuery {
+            typename,
+            filter,
+            start_index: None,
+            crs: None,
+            timeout: None,
+            output_format: WfsOutputFormat::GeoJson,
+        }
+    }
+}
+
+/// A feature type (layer) offered by the WFS
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct FeatureType {
+    /// Qualified name, e.g. `kadastralekaartv5:perceel`
+    pub name: String,
+    pub title: Option<String>,
+    pub default_crs: Option<String>,
+    pub other_crs: Vec<String>,
+}
+
+/// Parse the feature types from a WFS 2.0 GetCapabilities document.
+fn parse_capabilities(xml: &str) -> Result<Vec<FeatureType>, roxmltree::Error> {
+    let document = roxmltree::Document::parse(xml)?;
+
+    let child_text = |node: roxmltree::Node, name: &str| {
+        node.children()
+            .find(|child| child.tag_name().name() == name)
+            .and_then(|child| child.text())
+            .map(|text| text.trim().to_string())
+    };
+
+    let feature_types = document
+        .descendants()
+        .filter(|node| node.tag_name().name() == "FeatureType")
+        .filter_map(|node| {
+            Some(FeatureType {
+                name: child_text(node, "Name")?,
+                title: child_text(node, "Title"),
+                default_crs: child_text(node, "DefaultCRS"),
+                other_crs: node
+                    .children()
+                    .filter(|child| child.tag_name().name() == "OtherCRS")
+                    .filter_map(|child| child.text())
+                    .map(|text| text.trim().to_string())
+                    .collect(),
+            })
+        })
+        .collect();
+
+    Ok(feature_types)
+}
+
+/// The format of WFS GetFeature responses
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum WfsOutputFormat {
+    GeoJson,
+    /// GML 3.2, the default format of WFS 2.0
+    Gml32,
+    /// GML 2.1.2
+    Gml2,
+}
+
+impl WfsOutputFormat {
+    fn as_str(&self) -> &'static str {
+        match self {
+            WfsOutputFormat::GeoJson => "application/json",
+            WfsOutputFormat::Gml32 => "application/gml+xml; version=3.2",
+            WfsOutputFormat::Gml2 => "text/xml; subtype=gml/2.1.2",
+        }
+    }
+}
+
+/// A filter for WFS GetFeature requests
+#[derive(Clone, Debug)]
+pub enum WfsFilter {
+    /// An OGC filter encoding document, e.g. `<Filter>...</Filter>`
+    Xml(String),
+    /// A CQL expression, e.g. `kadastraleGrootteWaarde > 1000`
+    Cql(String),
+    /// A bounding box, in the coordinate space of the client
+    Bbox(Rect<f64>),
+}
+
+impl WfsFilter {
+    fn as_param(&self, crs: CoordinateSpace) -> (&'static str, String) {
+        match self {
+            WfsFilter::Xml(filter) => ("filter", filter.clone()),
+            WfsFilter::Cql(cql) => ("CQL_FILTER", cql.clone()),
+            WfsFilter::Bbox(bbox) => {
+                let (min, max) = (bbox.min(), bbox.max());
+
+                // Note that the urn notation of EPSG:4258 uses latitude, longitude order
+                let corners = match crs {
+                    CoordinateSpace::Rijksdriehoek => [min.x, min.y, max.x, max.y],
+                    CoordinateSpace::Gps => [min.y, min.x, max.y, max.x],
+                };
+
+                let [a, b, c, d] = corners;
+                ("bbox", format!("{a},{b},{c},{d},{}", crs.srs_name()))
+            }
+        }
+    }
+}
+
+/// Decode the lots in a perceel feature collection that was requested in `crs`,
+/// logging any features that were dropped.
+fn decode_lots(collection: FeatureCollection, crs: CoordinateSpace) -> Vec<Lot> {
+    let total = collection.features.len();
+    let lots: Vec<Lot> = collection
+        .features
+        .iter()
+        .filter_map(|feature| lot_from_feature(feature, crs))
+        .collect();
+
+    if lots.len() < total {
+        log::warn!(
+            "dropped {} of {} lot features that could not be decoded",
+            total - lots.len(),
+            total
+        );
+    }
+
+    lots
+}
+
+/// Decode a lot from a perceel feature that was requested in `crs`, yielding `None` when
+/// required properties are missing.
+fn lot_from_feature(feature: &Feature, crs: CoordinateSpace) -> Option<Lot> {
+    Some(Lot {
+        id: feature
+            .property("identificatieLokaalID")?
+            .as_str()?
+            .to_string(),
+        gemeentenaam: Some(
+            feature
+                .property("kadastraleGemeenteWaarde")?
+                .as_str()?
+                .to_string(),
+        ),
+        kadastralegemeentecode: Some(
+            feature
+                .property("AKRKadastraleGemeenteCodeWaarde")?
+                .as_str()?
+                .to_string(),
+        ),
+        grootte: numeric_property(feature, "kadastraleGrootteWaarde"),
+        sectie: Some(feature.property("sectie")?.as_str()?.to_string()),
+        perceelnummer: Some(numeric_property(feature, "perceelnummer")?),
+        eind_registratie: feature
+            .property("eindRegistratie")
+            .and_then(|value| value.as_str())
+            .map(str::to_string),
+        plaatscoordinaat: match (
+            numeric_property(feature, "perceelnummerPlaatscoordinaatX"),
+            numeric_property(feature, "perceelnummerPlaatscoordinaatY"),
+        ) {
+            (Some(x), Some(y)) => Some(plaatscoordinaat(x, y, crs)),
+            _ => None,
+        },
+        geometry: feature.geometry.clone()?,
+        crs,
+    })
+}
+
+/// The plaatscoordinaat attributes are always in rijksdriehoek, regardless of the
+/// requested CRS, so convert them to WGS84 when that was requested.
+fn plaatscoordinaat(x: f64, y: f64, crs: CoordinateSpace) -> Point<f64> {
+    match crs {
+        CoordinateSpace::Rijksdriehoek => Point::new(x, y),
+        CoordinateSpace::Gps => crate::util::coordinate_rijksdriehoek_to_wgs84(x, y).into(),
+    }
+}
+
+/// Read a numeric property, which PDOK occasionally serializes as a string (e.g. `"5038"`).
+fn numeric_property<T: std::str::FromStr>(feature: &Feature, name: &str) -> Option<T> {
+    match feature.property(name)? {
+        serde_json::Value::Number(number) => number.to_string().parse().ok(),
+        serde_json::Value::String(string) => string.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// The polygons that make up the geometry of a lot.
+fn lot_polygons(lot: &Lot) -> Option<Vec<geo::Polygon<f64>>> {
+    geojson_value_to_multipolygon(&lot.geometry.value)
+        .ok()
+        .map(|multi_polygon| multi_polygon.0)
+}
+
+/// A singular lot along with its geometry and size.
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct Lot {
+    /// The `identificatieLokaalID`, which uniquely identifies the lot
+    pub id: String,
+    #[serde(rename = "kadastraleGemeentenaam")]
+    pub gemeentenaam: Option<String>,
+    #[serde(rename = "kadastraleGemeentecode")]
+    pub kadastralegemeentecode: Option<String>,
+    /// The registered size in m², which may differ from the area of the geometry
+    #[serde(rename = "kadastraleGrootte")]
+    pub grootte: Option<f64>,
+    pub sectie: Option<String>,
+    pub perceelnummer: Option<u64>,
+    /// When the registration of the lot ended, e.g. because it was split or merged.
+    /// `None` for a current lot.
+    #[serde(rename = "eindRegistratie", default)]
+    pub eind_registratie: Option<String>,
+    /// Where the perceelnummer is placed on the kadastrale kaart, in the coordinate space
+    /// the lot was fetched in. PDOK always provides it in rijksdriehoek, so it is converted
+    /// when the lot is fetched in WGS84.
+    #[serde(default)]
+    pub plaatscoordinaat: Option<Point<f64>>,
+    /// The outline of the lot, in the coordinate space it was fetched in
+    pub geometry: Geometry,
+    /// The coordinate space the lot was fetched in, i.e. the `accept_crs` of the client or
+    /// the CRS passed to the query. Lots stored without it are taken to be in WGS84.
+    #[serde(default = "default_lot_crs")]
+    pub crs: CoordinateSpace,
+}
+
+/// The default `accept_crs` of [`BrkClientBuilder`]
+fn default_lot_crs() -> CoordinateSpace {
+    CoordinateSpace::Gps
+}
+
+impl Lot {
+    /// Construct a lot without fetching it, e.g. for testing code that consumes lots.
+    ///
+    /// The remaining fields are left empty and can be set directly. The geometry is taken
+    /// to be in WGS84, set [`Lot::crs`] for rijksdriehoek coordinates.
+    ///
+    /// ```
+    /// use pdok_apis::brk::Lot;
+    ///
+    /// let geometry = geojson::Geometry::new(geojson::Value::Polygon(vec![vec![
+    ///     vec![0.0, 0.0],
+    ///     vec![10.0, 0.0],
+    ///     vec![10.0, 10.0],
+    ///     vec![0.0, 10.0],
+    ///     vec![0.0, 0.0],
+    /// ]]));
+    /// let lot = Lot::from_parts("12345", geometry, Some(100.0));
+    ///
+    /// let centroid = lot.centroid().unwrap();
+    /// assert_eq!((centroid.x(), centroid.y()), (5.0, 5.0));
+    /// ```
+    pub fn from_parts(id: impl Into<String>, geometry: Geometry, grootte: Option<f64>) -> Self {
+        Lot {
+            id: id.into(),
+            gemeentenaam: None,
+            kadastralegemeentecode: None,
+            grootte,
+            sectie: None,
+            perceelnummer: None,
+            eind_registratie: None,
+            plaatscoordinaat: None,
+            geometry,
+            crs: default_lot_crs(),
+        }
+    }
+
+    /// The lot as a GeoJSON feature, with its other fields as properties, e.g. to show it
+    /// on a map.
+    pub fn to_feature(&self) -> Feature {
+        crate::geometry::to_feature(self, &self.id, &self.geometry)
+    }
+
+    /// The geometry as WKT, a `POLYGON` or, for a lot in several parts, a `MULTIPOLYGON`.
+    ///
+    /// The coordinates are in the CRS the client requested (its `accept_crs`) when the lot
+    /// was fetched, which is needed as SRID when loading it into PostGIS. Fails when the
+    /// geometry contains a malformed position.
+    pub fn to_wkt(&self) -> Result<String, crate::geometry::GeometryError> {
+        crate::geometry::geojson_geometry_to_wkt(&self.geometry)
+    }
+
+    /// Whether the lot is current, i.e. its registration has not ended.
+    pub fn is_active(&self) -> bool {
+        self.eind_registratie.is_none()
+    }
+
+    /// The kadastrale gemeente of the lot, if both its code and name are known.
+    pub fn gemeente(&self) -> Option<KadastraleGemeente> {
+        Some(KadastraleGemeente {
+            code: self.kadastralegemeentecode.clone()?,
+            naam: self.gemeentenaam.clone()?,
+        })
+    }
+
+    /// The centroid of the lot's geometry, in the coordinate space it was fetched in.
+    pub fn centroid(&self) -> Option<Point<f64>> {
+        use geo::algorithm::centroid::Centroid;
+
+        geojson_geometry_to_geo(&self.geometry).ok()?.centroid()
+    }
+
+    /// Where to put a label for the lot on a map: the [`Lot::plaatscoordinaat`] chosen by
+    /// the Kadaster, or the centroid when it is missing.
+    ///
+    /// Unlike the centroid, the plaatscoordinaat lies within the lot even when it is
+    /// strongly concave.
+    pub fn label_point(&self) -> Option<Point<f64>> {
+        self.plaatscoordinaat.or_else(|| self.centroid())
+    }
+
+    /// The coordinates of the outer ring of the lot, e.g. for drawing it without `geo`.
+    ///
+    /// For a lot consisting of several parts this is the ring of the largest part, see
+    /// [`Lot::exterior_rings`] for all of them. The ring is closed, so the first and last
+    /// coordinates are equal. Coordinates are in the coordinate space the lot was fetched in.
+    pub fn exterior_ring(&self) -> Option<Vec<Coord<f64>>> {
+        use geo::algorithm::area::Area;
+
+        lot_polygons(self)?
+            .into_iter()
+            .max_by(|a, b| a.unsigned_area().total_cmp(&b.unsigned_area()))
+            .map(|polygon| polygon.exterior().0.clone())
+    }
+
+    /// The coordinates of the outer rings of all parts of the lot.
+    pub fn exterior_rings(&self) -> Option<Vec<Vec<Coord<f64>>>> {
+        let polygons = lot_polygons(self)?;
+
+        Some(
+            polygons
+                .iter()
+                .map(|polygon| polygon.exterior().0.clone())
+                .collect(),
+        )
+    }
+
+    /// Whether a point in the given coordinate space lies within the lot, e.g. to check
+    /// whether a GPS position is on the lot.
+    ///
+    /// The point is converted when the lot was fetched in the other coordinate space
+    /// (see [`Lot::crs`]).
+    /// Like `geo`'s `Contains`, points exactly on the boundary are not within the lot.
+    pub fn contains_point(&self, point: Point<f64>, space: CoordinateSpace) -> bool {
+        use crate::util::{coordinate_rijksdriehoek_to_wgs84, coordinate_wgs84_to_rijksdriehoek};
+        use geo::algorithm::contains::Contains;
+
+        let polygons = match lot_polygons(self) {
+            Some(polygons) => polygons,
+            None => return false,
+        };
+        let point = match (space, self.crs) {
+            (CoordinateSpace::Gps, CoordinateSpace::Rijksdriehoek) => {
+                coordinate_wgs84_to_rijksdriehoek(point.x(), point.y()).into()
+            }
+            (CoordinateSpace::Rijksdriehoek, CoordinateSpace::Gps) => {
+                coordinate_rijksdriehoek_to_wgs84(point.x(), point.y()).into()
+            }
+            _ => point,
+        };
+
+        polygons.iter().any(|polygon| polygon.contains(&point))
+    }
+
+    /// The length of the boundary of the lot in meters, i.e. of the outer rings of its
+    /// parts, computed from the geometry.
+    ///
+    /// Lengths are planar for rijksdriehoek coordinates and along the great circle for
+    /// WGS84, which agree within centimeters for a lot. See [`Lot::perimeter_with_holes`]
+    /// to include the boundaries of enclaves.
+    pub fn perimeter(&self) -> Option<f64> {
+        self.boundary_length(false)
+    }
+
+    /// Like [`Lot::perimeter`], also counting the inner rings, i.e. the boundaries with
+    /// other lots enclosed by this one.
+    pub fn perimeter_with_holes(&self) -> Option<f64> {
+        self.boundary_length(true)
+    }
+
+    fn boundary_length(&self, include_holes: bool) -> Option<f64> {
+        use geo::algorithm::{
+            euclidean_length::EuclideanLength, haversine_length::HaversineLength,
+        };
+
+        let polygons = lot_polygons(self)?;
+        let space = self.crs;
+
+        let rings = polygons.iter().flat_map(|polygon| {
+            let holes = if include_holes {
+                polygon.interiors()
+            } else {
+                &[]
+            };
+            std::iter::once(polygon.exterior()).chain(holes)
+        });
+
+        Some(
+            rings
+                .map(|ring| match space {
+                    CoordinateSpace::Rijksdriehoek => ring.euclidean_length(),
+                    CoordinateSpace::Gps => ring.haversine_length(),
+                })
+                .sum(),
+        )
+    }
+
+    /// The registered size in hectares.
+    pub fn grootte_hectares(&self) -> Option<f64> {
+        Some(self.grootte? / 10_000.0)
+    }
+
+    /// The registered size in the traditional hectares, ares and centiares notation,
+    /// e.g. 5038 m² is `(0, 50, 38)`. Fractions of a m² are rounded.
+    pub fn grootte_ha_a_ca(&self) -> Option<(u32, u32, u32)> {
+        let centiares = self.grootte?.round() as u32;
+
+        Some((centiares / 10_000, centiares / 100 % 100, centiares % 100))
+    }
+}
+
+/// A kadastrale gemeente, the cadastral municipality a lot is registered in.
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KadastraleGemeente {
+    /// The AKR code, e.g. `HTT02`
+    pub code: String,
+    /// The name, e.g. `Hatert`
+    pub naam: String,
+}
+
+impl PartialEq for Lot {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for Lot {}
+
+impl std::hash::Hash for Lot {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl PartialOrd for Lot {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Lot {
+    fn cmp(&self, other: &Self) -> Ordering {
+        if self.eq(other) {
+            Ordering::Equal
+        } else if self.id < other.id {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::ClientBuilder;
+
+    macro_rules! aw {
+        ($e:expr) => {
+            tokio_test::block_on($e)
+        };
+    }
+
+    #[test]
+    fn test_get_lot() {
+        let brk_client = BrkClientBuilder::new()
+            .accept_crs(CoordinateSpace::Rijksdriehoek)
+            .build();
+
+        let result = aw!(brk_client.get_lot("HTT02", "M", "5038"));
+        assert_eq!(result.is_ok(), true);
+    }
+
+    #[test]
+    fn test_get_lot_by_id() {
+        let brk_client = BrkClientBuilder::new().build();
+
+        let lot = aw!(brk_client.get_lot("HTT02", "M", "5038"))
+            .unwrap()
+            .remove(0);
+        let refetched = aw!(brk_client.get_lot_by_id(&lot.id)).unwrap().unwrap();
+
+        assert_eq!(refetched, lot);
+        assert_eq!(refetched.perceelnummer, Some(5038));
+    }
+
+    #[test]
+    fn test_plaatscoordinaat() {
+        let brk_client = BrkClientBuilder::new()
+            .accept_crs(CoordinateSpace::Rijksdriehoek)
+            .build();
+
+        let lot = aw!(brk_client.get_lot("HTT02", "M", "5038"))
+            .unwrap()
+            .remove(0);
+        let plaatscoordinaat = lot.plaatscoordinaat.unwrap();
+
+        assert!(lot.contains_point(plaatscoordinaat, CoordinateSpace::Rijksdriehoek));
+        assert_eq!(lot.label_point(), Some(plaatscoordinaat));
+    }
+
+    #[test]
+    fn test_get_lot_by_id_mock() {
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(wiremock::matchers::any())
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "type": "FeatureCollection",
+                    "features": []
+                })))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let brk_client = BrkClientBuilder::new().base_url(&uri).build();
+
+            let lot = brk_client.get_lot_by_id("70860503870000").await.unwrap();
+            assert!(lot.is_none());
+
+            let requests = server.received_requests().await.unwrap();
+            let url = requests[0].url.to_string();
+            assert!(url.contains("identificatieLokaalID"));
+            assert!(url.contains("70860503870000"));
+        });
+    }
+
+    #[test]
+    fn test_get_lots() {
+        let brk_client = BrkClientBuilder::new().build();
+        let key = |sectie: &str, perceelnummer: &str| {
+            (
+                "HTT02".to_string(),
+                sectie.to_string(),
+                perceelnummer.to_string(),
+            )
+        };
+
+        let results = aw!(brk_client.get_lots(&[key("Z", "1"), key("M", "5038")]));
+
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_err());
+        assert_eq!(results[1].as_ref().unwrap()[0].perceelnummer, Some(5038));
+    }
+
+    #[test]
+    fn test_get_lots_order() {
+        use std::time::Duration;
+        use wiremock::{Mock, MockServer, Request, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(wiremock::matchers::any())
+                .respond_with(|request: &Request| {
+                    let perceelnummer = if request.url.as_str().contains("5038") {
+                        5038
+                    } else {
+                        1234
+                    };
+                    let collection = serde_json::json!({
+                        "type": "FeatureCollection",
+                        "features": [test_feature("1", perceelnummer.into())]
+                    });
+
+                    // The first lot is answered last
+                    let delay = if perceelnummer == 5038 { 200 } else { 0 };
+                    ResponseTemplate::new(200)
+                        .set_body_json(collection)
+                        .set_delay(Duration::from_millis(delay))
+                })
+                .expect(2)
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let brk_client = BrkClientBuilder::new().base_url(&uri).build();
+            let keys = [
+                ("HTT02".to_string(), "M".to_string(), "5038".to_string()),
+                ("HTT02".to_string(), "M".to_string(), "1234".to_string()),
+            ];
+
+            let results = brk_client.get_lots(&keys).await;
+
+            let perceelnummers: Vec<_> = results
+                .into_iter()
+                .map(|result| result.unwrap()[0].perceelnummer)
+                .collect();
+            assert_eq!(perceelnummers, [Some(5038), Some(1234)]);
+        });
+    }
+
+    #[test]
+    fn test_get_lot_peildatum() {
+        // The TG office lot did not exist yet in 1900
+        let brk_client = BrkClientBuilder::new()
+            .peildatum(NaiveDate::from_ymd_opt(1900, 1, 1).unwrap())
+            .build();
+        let result = aw!(brk_client.get_lot("HTT02", "M", "5038"));
+        assert!(matches!(result, Err(Error::EmptyResponse)));
+
+        let brk_client = BrkClientBuilder::new()
+            .peildatum(chrono::Utc::now().date_naive())
+            .build();
+        let result = aw!(brk_client.get_lot("HTT02", "M", "5038"));
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_get_lot_peildatum_grootte() {
+        use wiremock::{matchers, Mock, MockServer, Request, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            // The lot grew when a neighbouring lot was merged into it in 2015
+            Mock::given(matchers::query_param("request", "GetFeature"))
+                .respond_with(|request: &Request| {
+                    let filter = request
+                        .url
+                        .query_pairs()
+                        .find(|(key, _)| key == "filter")
+                        .map(|(_, filter)| filter.into_owned())
+                        .unwrap_or_default();
+                    let grootte = match filter {
+                        f if f.contains("<Literal>2010-01-01</Literal>") => "1000",
+                        f if f.contains("<Literal>2020-01-01</Literal>") => "1500",
+                        _ => return ResponseTemplate::new(500),
+                    };
+
+                    let mut feature = test_feature("1", serde_json::json!(5038));
+                    feature["properties"]["kadastraleGrootteWaarde"] = serde_json::json!(grootte);
+                    let page = serde_json::json!({
+                        "type": "FeatureCollection",
+                        "features": [feature]
+                    });
+
+                    ResponseTemplate::new(200).set_body_json(page)
+                })
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let grootte_at = |peildatum: NaiveDate| {
+                let brk_client = BrkClientBuilder::new()
+                    .base_url(&uri)
+                    .peildatum(peildatum)
+                    .build();
+
+                async move { brk_client.get_lot("HTT02", "M", "5038").await.unwrap()[0].grootte }
+            };
+
+            let before = grootte_at(NaiveDate::from_ymd_opt(2010, 1, 1).unwrap()).await;
+            let after = grootte_at(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap()).await;
+
+            assert_eq!(before, Some(1000.0));
+            assert_eq!(after, Some(1500.0));
+        });
+    }
+
+    fn test_feature(id: &str, perceelnummer: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({
+            "type": "Feature",
+            "geometry": {
+                "type": "Polygon",
+                "coordinates": [[[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 0.0]]]
+            },
+            "properties": {
+                "identificatieLokaalID": id,
+                "kadastraleGemeenteWaarde": "Nijmegen",
+                "AKRKadastraleGemeenteCodeWaarde": "HTT02",
+                "kadastraleGrootteWaarde": "1234",
+                "sectie": "M",
+                "perceelnummer": perceelnummer
+            }
+        })
+    }
+
+    #[test]
+    fn test_lot_from_feature_string_numbers() {
+        let feature: Feature =
+            serde_json::from_value(test_feature("12345", serde_json::json!("5038"))).unwrap();
+
+        let lot = lot_from_feature(&feature, CoordinateSpace::Gps).unwrap();
+        assert_eq!(lot.perceelnummer, Some(5038));
+        assert_eq!(lot.grootte, Some(1234.0));
+    }
+
+    #[test]
+    fn test_label_point() {
+        let mut feature = test_feature("12345", serde_json::json!(5038));
+        feature["geometry"]["coordinates"] = serde_json::json!([[
+            [187_000.0, 428_000.0],
+            [187_001.0, 428_000.0],
+            [187_001.0, 428_001.0],
+            [187_000.0, 428_000.0]
+        ]]);
+        let rd = CoordinateSpace::Rijksdriehoek;
+        let lot = lot_from_feature(&serde_json::from_value(feature.clone()).unwrap(), rd).unwrap();
+        assert_eq!(lot.plaatscoordinaat, None);
+        assert_eq!(lot.label_point(), lot.centroid());
+
+        feature["properties"]["perceelnummerPlaatscoordinaatX"] = serde_json::json!(187_000.75);
+        feature["properties"]["perceelnummerPlaatscoordinaatY"] = serde_json::json!("428000.25");
+        let lot = lot_from_feature(&serde_json::from_value(feature).unwrap(), rd).unwrap();
+        assert_eq!(lot.label_point(), Some(Point::new(187_000.75, 428_000.25)));
+    }
+
+    #[test]
+    fn test_plaatscoordinaat_wgs84() {
+        let mut feature = test_feature("12345", serde_json::json!(5038));
+        feature["properties"]["perceelnummerPlaatscoordinaatX"] = serde_json::json!(187_000.0);
+        feature["properties"]["perceelnummerPlaatscoordinaatY"] = serde_json::json!(428_000.0);
+        let feature: Feature = serde_json::from_value(feature).unwrap();
+
+        // Converted by the requested CRS, whatever the coordinates of the geometry look like
+        let lot = lot_from_feature(&feature, CoordinateSpace::Gps).unwrap();
+        let expected = crate::util::coordinate_rijksdriehoek_to_wgs84(187_000.0, 428_000.0);
+        assert_eq!(lot.plaatscoordinaat, Some(expected.into()));
+        assert_eq!(lot.crs, CoordinateSpace::Gps);
+
+        let lot = lot_from_feature(&feature, CoordinateSpace::Rijksdriehoek).unwrap();
+        assert_eq!(lot.plaatscoordinaat, Some(Point::new(187_000.0, 428_000.0)));
+    }
+
+    #[test]
+    fn test_grootte_conversions() {
+        let geometry = Geometry::new(geojson::Value::Point(vec![0.0, 0.0]));
+
+        let lot = Lot::from_parts("12345", geometry.clone(), Some(5038.0));
+        assert_eq!(lot.grootte_hectares(), Some(0.5038));
+        assert_eq!(lot.grootte_ha_a_ca(), Some((0, 50, 38)));
+
+        let lot = Lot::from_parts("12345", geometry.clone(), Some(30_000.0));
+        assert_eq!(lot.grootte_hectares(), Some(3.0));
+        assert_eq!(lot.grootte_ha_a_ca(), Some((3, 0, 0)));
+
+        let lot = Lot::from_parts("12345", geometry, None);
+        assert_eq!(lot.grootte_hectares(), None);
+        assert_eq!(lot.grootte_ha_a_ca(), None);
+    }
+
+    #[test]
+    fn test_lot_hash() {
+        use std::collections::HashSet;
+
+        let geometry = Geometry::new(geojson::Value::Point(vec![0.0, 0.0]));
+        let lot = Lot::from_parts("12345", geometry.clone(), Some(5038.0));
+        let duplicate = Lot::from_parts("12345", geometry.clone(), None);
+        let other = Lot::from_parts("12346", geometry, None);
+
+        let lots: HashSet<Lot> = [lot, duplicate, other].into_iter().collect();
+        assert_eq!(lots.len(), 2);
+    }
+
+    #[test]
+    fn test_lot_to_feature() {
+        let geometry = Geometry::new(geojson::Value::Point(vec![0.0, 0.0]));
+        let mut lot = Lot::from_parts("12345", geometry.clone(), Some(5038.0));
+        lot.sectie = Some("M".to_string());
+
+        let feature = lot.to_feature();
+        let properties = feature.properties.unwrap();
+
+        assert_eq!(feature.geometry, Some(geometry));
+        assert_eq!(properties["id"], "12345");
+        assert_eq!(properties["sectie"], "M");
+        assert_eq!(properties["kadastraleGrootte"], 5038.0);
+        assert!(!properties.contains_key("geometry"));
+    }
+
+    #[test]
+    fn test_perimeter() {
+        let rectangle = |x: f64, y: f64, width: f64, height: f64| {
+            vec![
+                vec![x, y],
+                vec![x + width, y],
+                vec![x + width, y + height],
+                vec![x, y + height],
+                vec![x, y],
+            ]
+        };
+
+        // A 40 x 25 m lot with a 10 x 5 m enclave
+        let geometry = Geometry::new(geojson::Value::Polygon(vec![
+            rectangle(187_000.0, 428_000.0, 40.0, 25.0),
+            rectangle(187_010.0, 428_010.0, 10.0, 5.0),
+        ]));
+        let mut lot = Lot::from_parts("12345", geometry, Some(1000.0));
+        lot.crs = CoordinateSpace::Rijksdriehoek;
+
+        assert_eq!(lot.perimeter(), Some(130.0));
+        assert_eq!(lot.perimeter_with_holes(), Some(160.0));
+
+        // About 34 x 22 m in Nijmegen, in WGS84
+        let geometry = Geometry::new(geojson::Value::Polygon(vec![rectangle(
+            5.861, 51.837, 0.0005, 0.0002,
+        )]));
+        let lot = Lot::from_parts("12345", geometry, Some(750.0));
+
+        assert!((lot.perimeter().unwrap() - 113.185).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_lot_to_wkt() {
+        use crate::geometry::{geojson_geometry_to_geo, wkt_to_geo};
+
+        let feature: Feature =
+            serde_json::from_value(test_feature("12345", serde_json::json!(5038))).unwrap();
+        let lot = lot_from_feature(&feature, CoordinateSpace::Gps).unwrap();
+
+        let wkt = lot.to_wkt().unwrap();
+
+        assert!(wkt.starts_with("POLYGON"));
+        assert_eq!(
+            wkt_to_geo(&wkt).unwrap(),
+            geojson_geometry_to_geo(&lot.geometry).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_neighbors() {
+        let brk_client = BrkClientBuilder::new()
+            .accept_crs(CoordinateSpace::Rijksdriehoek)
+            .build();
+
+        let lot = aw!(brk_client.get_lot("HTT02", "M", "5038"))
+            .unwrap()
+            .remove(0);
+        let mut neighbors = aw!(brk_client.get_neighbors(&lot)).unwrap();
+
+        assert!(!neighbors.is_empty());
+        assert!(neighbors.iter().all(|neighbor| neighbor.id != lot.id));
+
+        let count = neighbors.len();
+        neighbors.sort();
+        neighbors.dedup();
+        assert_eq!(neighbors.len(), count);
+    }
+
+    #[test]
+    fn test_lots_stream_first_page() {
+        use futures::StreamExt;
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            let page = serde_json::json!({
+                "type": "FeatureCollection",
+                "features": [
+                    test_feature("1", serde_json::json!(1)),
+                    test_feature("2", serde_json::json!(2)),
+                ]
+            });
+
+            Mock::given(matchers::query_param("startIndex", "0"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(page))
+                .expect(1)
+                .mount(&server)
+                .await;
+            Mock::given(matchers::query_param("startIndex", "2"))
+                .respond_with(ResponseTemplate::new(500))
+                .expect(0)
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let brk_client = BrkClientBuilder::new().base_url(&uri).page_size(2).build();
+
+            let filter = WfsFilter::Cql("sectie = 'M'".to_string());
+            let lots: Vec<_> = brk_client.lots_stream(filter).take(2).collect().await;
+
+            assert_eq!(lots.len(), 2);
+            assert_eq!(lots[0].as_ref().unwrap().id, "1");
+            assert_eq!(lots[1].as_ref().unwrap().id, "2");
+        });
+    }
+
+    #[test]
+    fn test_download_lots() {
+        use futures::StreamExt;
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            let page = |ids: &[&str]| {
+                let features: Vec<_> = ids
+                    .iter()
+                    .map(|id| test_feature(id, serde_json::json!(1)))
+                    .collect();
+                serde_json::json!({ "type": "FeatureCollection", "features": features })
+            };
+
+            Mock::given(matchers::query_param("startIndex", "0"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(page(&["1", "2"])))
+                .mount(&server)
+                .await;
+            // The second page fails once, after which it is requested again
+            Mock::given(matchers::query_param("startIndex", "2"))
+                .respond_with(ResponseTemplate::new(503))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+            Mock::given(matchers::query_param("startIndex", "2"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(page(&["3", "4"])))
+                .mount(&server)
+                .await;
+            Mock::given(matchers::query_param("startIndex", "4"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(page(&["5"])))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let brk_client = BrkClientBuilder::new().base_url(&uri).page_size(2).build();
+
+            let filter = WfsFilter::Cql("sectie = 'M'".to_string());
+            let mut lots = Box::pin(brk_client.download_lots(filter));
+            let mut ids = Vec::new();
+
+            while let Some(lot) = lots.next().await {
+                ids.push(lot.unwrap().id);
+
+                // Pages are only requested once the previous one has been consumed
+                let requests = server.received_requests().await.unwrap().len();
+                let expected = match ids.len() {
+                    1 | 2 => 1,
+                    3 | 4 => 3,
+                    _ => 4,
+                };
+                assert_eq!(requests, expected, "after {} lots", ids.len());
+            }
+
+            assert_eq!(ids, ["1", "2", "3", "4", "5"]);
+        });
+    }
+
+    #[test]
+    fn test_zero_page_size() {
+        use futures::StreamExt;
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            let page = |features: Vec<serde_json::Value>| serde_json::json!({ "type": "FeatureCollection", "features": features });
+
+            Mock::given(matchers::query_param("startIndex", "0"))
+                .and(matchers::query_param("count", "1"))
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(page(vec![test_feature("1", serde_json::json!(1))])),
+                )
+                .mount(&server)
+                .await;
+            Mock::given(matchers::query_param("startIndex", "1"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(page(vec![])))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let brk_client = BrkClientBuilder::new().base_url(&uri).page_size(0).build();
+
+            let filter = WfsFilter::Cql("sectie = 'M'".to_string());
+            let streamed: Vec<_> = brk_client.lots_stream(filter.clone()).collect().await;
+            assert_eq!(streamed.len(), 1);
+
+            let downloaded: Vec<_> = brk_client.download_lots(filter).collect().await;
+            assert_eq!(downloaded.len(), 1);
+        });
+    }
+
+    #[test]
+    fn test_validate_geometry() {
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            // The ring of the test feature is a valid triangle, make it a line instead
+            let mut feature = test_feature("12345", serde_json::json!(5038));
+            feature["geometry"]["coordinates"] =
+                serde_json::json!([[[0.0, 0.0], [1.0, 0.0], [0.0, 0.0]]]);
+
+            Mock::given(wiremock::matchers::any())
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "type": "FeatureCollection",
+                    "features": [feature]
+                })))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+
+            let brk_client = BrkClientBuilder::new().base_url(&uri).build();
+            assert!(brk_client.get_lot("HTT02", "M", "5038").await.is_ok());
+
+            let brk_client = BrkClientBuilder::new()
+                .base_url(&uri)
+                .validate_geometry(true)
+                .build();
+            let result = brk_client.get_lot("HTT02", "M", "5038").await;
+            assert!(matches!(
+                result,
+                Err(Error::InvalidGeometry { id, error: crate::util::GeometryValidationError::TooFewVertices(3) })
+                    if id == "12345"
+            ));
+        });
+    }
+
+    #[test]
+    fn test_download_lots_gives_up() {
+        use futures::StreamExt;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(wiremock::matchers::any())
+                .respond_with(ResponseTemplate::new(503))
+                .expect(3)
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let brk_client = BrkClientBuilder::new()
+                .base_url(&uri)
+                .max_retries(2)
+                .build();
+
+            let filter = WfsFilter::Cql("sectie = 'M'".to_string());
+            let lots: Vec<_> = brk_client.download_lots(filter).collect().await;
+
+            assert_eq!(lots.len(), 1);
+            assert!(matches!(lots[0], Err(Error::HttpStatus { code: 503, .. })));
+        });
+    }
+
+    #[test]
+    fn test_no_chunked_transfer_by_default() {
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            let page = serde_json::json!({
+                "type": "FeatureCollection",
+                "features": [test_feature("1", serde_json::json!(5038))]
+            });
+
+            Mock::given(wiremock::matchers::any())
+                .respond_with(ResponseTemplate::new(200).set_body_json(page))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let brk_client = BrkClientBuilder::new().base_url(&uri).build();
+
+            brk_client.get_lot("HTT02", "M", "5038").await.unwrap();
+
+            let requests = server.received_requests().await.unwrap();
+            assert!(!requests.is_empty());
+            assert!(requests.iter().all(|request| request
+                .headers
+                .keys()
+                .all(|name| name.as_str() != "transfer-encoding")));
+        });
+    }
+
+    #[test]
+    fn test_active_only() {
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            let mut historical = test_feature("2", serde_json::json!(5037));
+            historical["properties"]["eindRegistratie"] = "2019-04-01T10:12:00".into();
+            let page = serde_json::json!({
+                "type": "FeatureCollection",
+                "features": [test_feature("1", serde_json::json!(5038)), historical]
+            });
+
+            Mock::given(wiremock::matchers::any())
+                .respond_with(ResponseTemplate::new(200).set_body_json(page))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let filter = WfsFilter::Cql("sectie = 'M'".to_string());
+
+            let all_client = BrkClientBuilder::new().base_url(&uri).build();
+            let lots = all_client
+                .get_lots_filtered(all_client.perceel_query(&filter))
+                .await
+                .unwrap();
+            assert_eq!(lots.len(), 2);
+            assert!(!lots[1].is_active());
+
+            let active_client = BrkClientBuilder::new()
+                .base_url(&uri)
+                .active_only(true)
+                .build();
+            let lots = active_client
+                .get_lots_filtered(active_client.perceel_query(&filter))
+                .await
+                .unwrap();
+            assert_eq!(lots.len(), 1);
+            assert!(lots.iter().all(Lot::is_active));
+        });
+    }
+
+    #[test]
+    fn test_typename_override() {
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            let page = serde_json::json!({
+                "type": "FeatureCollection",
+                "features": [test_feature("1", serde_json::json!(5038))]
+            });
+
+            Mock::given(matchers::query_param(
+                "typenames",
+                "kadastralekaartv6:perceel",
+            ))
+            .and(matchers::query_param("version", "1.1.0"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(page))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+            let uri = server.uri();
+            let brk_client = BrkClientBuilder::new()
+                .base_url(&uri)
+                .typename("kadastralekaartv6:perceel")
+                .wfs_version("1.1.0")
+                .build();
+
+            let lots = brk_client.get_lot("HTT02", "M", "5038").await.unwrap();
+            assert_eq!(lots[0].id, "1");
+        });
+    }
+
+    #[test]
+    fn test_probe_timeout() {
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(wiremock::matchers::any())
+                .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let brk_client = BrkClientBuilder::new().base_url(&uri).build();
+
+            let start = std::time::Instant::now();
+            let result = brk_client.probe(Duration::from_millis(100)).await;
+
+            assert!(matches!(result, Err(Error::Timeout(_))));
+            assert!(start.elapsed() < Duration::from_secs(2));
+        });
+    }
+
+    #[test]
+    fn test_get_lot_with_timeout() {
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(wiremock::matchers::any())
+                .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let brk_client = BrkClientBuilder::new()
+                .base_url(&uri)
+                .request_timeout_secs(20)
+                .build();
+
+            let result = brk_client
+                .get_lot_with_timeout("HTT02", "M", "5038", Duration::from_millis(100))
+                .await;
+
+            assert!(matches!(result, Err(Error::Timeout(_))));
+        });
+    }
+
+    const CAPABILITIES: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<wfs:WFS_Capabilities xmlns:wfs="http://www.opengis.net/wfs/2.0" version="2.0.0">
+  <wfs:FeatureTypeList>
+    <wfs:FeatureType>
+      <wfs:Name>kadastralekaartv5:perceel</wfs:Name>
+      <wfs:Title>Perceel</wfs:Title>
+      <wfs:DefaultCRS>urn:ogc:def:crs:EPSG::28992</wfs:DefaultCRS>
+      <wfs:OtherCRS>urn:ogc:def:crs:EPSG::4258</wfs:OtherCRS>
+      <wfs:OtherCRS>urn:ogc:def:crs:EPSG::4326</wfs:OtherCRS>
+    </wfs:FeatureType>
+  </wfs:FeatureTypeList>
+</wfs:WFS_Capabilities>"#;
+
+    #[test]
+    fn test_parse_capabilities() {
+        let feature_types = parse_capabilities(CAPABILITIES).unwrap();
+        assert_eq!(
+            feature_types,
+            vec![FeatureType {
+                name: "kadastralekaartv5:perceel".to_string(),
+                title: Some("Perceel".to_string()),
+                default_crs: Some("urn:ogc:def:crs:EPSG::28992".to_string()),
+                other_crs: vec![
+                    "urn:ogc:def:crs:EPSG::4258".to_string(),
+                    "urn:ogc:def:crs:EPSG::4326".to_string()
+                ],
+            }]
+        );
+    }
+
+    #[test]
+    fn test_get_capabilities() {
+        let brk_client = BrkClientBuilder::new().build();
+
+        let feature_types = aw!(brk_client.get_capabilities()).unwrap();
+        assert!(feature_types
+            .iter()
+            .any(|feature_type| feature_type.name == "kadastralekaartv5:perceel"));
+    }
+
+    #[test]
+    fn test_supported_crs() {
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(matchers::query_param("request", "GetCapabilities"))
+                .respond_with(ResponseTemplate::new(200).set_body_string(CAPABILITIES))
+                .expect(2)
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let brk_client = BrkClientBuilder::new().base_url(&uri).build();
+
+            let supported_crs = brk_client.supported_crs().await.unwrap();
+            assert_eq!(supported_crs[0], "urn:ogc:def:crs:EPSG::28992");
+            assert_eq!(supported_crs.len(), 3);
+
+            // Fetched once per client, shared by its clones
+            let cached = brk_client.clone().supported_crs().await.unwrap();
+            assert_eq!(cached, supported_crs);
+
+            let other = BrkClientBuilder::new()
+                .base_url(&uri)
+                .typename("kadastralekaartv5:bebouwing")
+                .build();
+            assert!(other.supported_crs().await.unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn test_get_features_bbox() {
+        use geo::algorithm::bounding_rect::BoundingRect;
+
+        let brk_client = BrkClientBuilder::new()
+            .accept_crs(CoordinateSpace::Rijksdriehoek)
+            .build();
+
+        let lot = aw!(brk_client.get_lot("HTT02", "M", "5038"))
+            .unwrap()
+            .remove(0);
+        let bbox = lot_polygons(&lot).unwrap()[0].bounding_rect().unwrap();
+
+        let filter = WfsFilter::Bbox(bbox);
+        let collection =
+            aw!(brk_client.get_features("kadastralekaartv5:kadastralegrens", filter)).unwrap();
+
+        assert!(!collection.features.is_empty());
+    }
+
+    #[test]
+    fn test_lot_centroid() {
+        use geo::algorithm::contains::Contains;
+
+        let brk_client = BrkClientBuilder::new()
+            .accept_crs(CoordinateSpace::Rijksdriehoek)
+            .build();
+
+        let lot = aw!(brk_client.get_lot("HTT02", "M", "5038"))
+            .unwrap()
+            .remove(0);
+        let centroid = lot.centroid().unwrap();
+
+        assert!(lot_polygons(&lot).unwrap()[0].contains(&centroid));
+    }
+
+    #[test]
+    fn test_lot_contains_point() {
+        let geometry = Geometry::new(geojson::Value::Polygon(vec![vec![
+            vec![187_000.0, 428_000.0],
+            vec![187_100.0, 428_000.0],
+            vec![187_100.0, 428_100.0],
+            vec![187_000.0, 428_100.0],
+            vec![187_000.0, 428_000.0],
+        ]]));
+        let mut lot = Lot::from_parts("12345", geometry, None);
+        lot.crs = CoordinateSpace::Rijksdriehoek;
+        let rd = CoordinateSpace::Rijksdriehoek;
+
+        assert!(lot.contains_point(Point::new(187_050.0, 428_050.0), rd));
+        assert!(!lot.contains_point(Point::new(187_000.0, 428_050.0), rd));
+        assert!(!lot.contains_point(Point::new(121_000.0, 487_000.0), rd));
+    }
+
+    #[test]
+    fn test_lot_exterior_ring() {
+        let square = |size: f64| {
+            vec![vec![
+                vec![0.0, 0.0],
+                vec![size, 0.0],
+                vec![size, size],
+                vec![0.0, size],
+                vec![0.0, 0.0],
+            ]]
+        };
+        let geometry = Geometry::new(geojson::Value::MultiPolygon(vec![
+            square(1.0),
+            square(10.0),
+        ]));
+        let lot = Lot::from_parts("12345", geometry, None);
+
+        let ring = lot.exterior_ring().unwrap();
+        assert_eq!(ring.len(), 5);
+        assert_eq!(ring.first(), ring.last());
+        assert_eq!(ring[2], Coord { x: 10.0, y: 10.0 });
+
+        assert_eq!(lot.exterior_rings().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_lot_contains_gps_point() {
+        let brk_client = BrkClientBuilder::new()
+            .accept_crs(CoordinateSpace::Rijksdriehoek)
+            .build();
+
+        let lot = aw!(brk_client.get_lot("HTT02", "M", "5038"))
+            .unwrap()
+            .remove(0);
+        let centroid = lot.centroid().unwrap();
+        let gps = crate::util::coordinate_rijksdriehoek_to_wgs84(centroid.x(), centroid.y());
+
+        assert!(lot.contains_point(centroid, CoordinateSpace::Rijksdriehoek));
+        assert!(lot.contains_point(gps.into(), CoordinateSpace::Gps));
+        // The Dam in Amsterdam
+        assert!(!lot.contains_point(Point::new(4.8932, 52.3731), CoordinateSpace::Gps));
+    }
+
+    #[test]
+    #[cfg(feature = "cache")]
+    fn test_get_lot_cached() {
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            let page = serde_json::json!({
+                "type": "FeatureCollection",
+                "features": [test_feature("1", serde_json::json!(5038))]
+            });
+
+            Mock::given(matchers::query_param("request", "GetFeature"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(page))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let brk_client = BrkClientBuilder::new()
+                .base_url(&uri)
+                .cache_capacity(10)
+                .build();
+
+            let first = brk_client.get_lot("HTT02", "M", "5038").await.unwrap();
+            let second = brk_client.get_lot("HTT02", "M", "5038").await.unwrap();
+            assert_eq!(first, second);
+        });
+    }
+
+    #[test]
+    fn test_lot_gemeente() {
+        let brk_client = BrkClientBuilder::new().build();
+
+        let mut lot = aw!(brk_client.get_lot("HTT02", "M", "5038"))
+            .unwrap()
+            .remove(0);
+
+        let gemeente = lot.gemeente().unwrap();
+        assert_eq!(gemeente.code, "HTT02");
+        assert_eq!(gemeente.naam, "Hatert");
+
+        lot.gemeentenaam = None;
+        assert_eq!(lot.gemeente(), None);
+    }
+
+    #[test]
+    fn test_get_lot_with_crs() {
+        let brk_client = BrkClientBuilder::new().build();
+
+        let rd =
+            aw!(brk_client.get_lot_with_crs("HTT02", "M", "5038", CoordinateSpace::Rijksdriehoek))
+                .unwrap()
+                .remove(0)
+                .centroid()
+                .unwrap();
+        let gps = aw!(brk_client.get_lot_with_crs("HTT02", "M", "5038", CoordinateSpace::Gps))
+            .unwrap()
+            .remove(0)
+            .centroid()
+            .unwrap();
+
+        // Nijmegen is roughly at (187000, 428000) in rijksdriehoek, and (5.85, 51.84) in gps
+        assert!((rd.x() - 187_000.0).abs() < 5_000.0 && (rd.y() - 428_000.0).abs() < 5_000.0);
+        assert!((gps.x() - 5.85).abs() < 0.1 && (gps.y() - 51.84).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_get_lot_raw() {
+        let brk_client = BrkClientBuilder::new().build();
+
+        let gml =
+            aw!(brk_client.get_lot_raw("HTT02", "M", "5038", WfsOutputFormat::Gml32)).unwrap();
+        assert!(gml.trim_start().starts_with("<?xml"));
+        assert!(gml.contains("wfs:FeatureCollection"));
+
+        let json =
+            aw!(brk_client.get_lot_raw("HTT02", "M", "5038", WfsOutputFormat::GeoJson)).unwrap();
+        assert!(json.contains("FeatureCollection"));
+    }
+
+    #[test]
+    fn test_get_lots_cql() {
+        let brk_client = BrkClientBuilder::new().build();
+
+        let cql = "AKRKadastraleGemeenteCodeWaarde = 'HTT02' AND sectie = 'M' AND kadastraleGrootteWaarde > 1000";
+        let lots = aw!(brk_client.get_lots_cql(cql)).unwrap();
+
+        assert!(!lots.is_empty());
+        assert!(lots.iter().all(|lot| lot.grootte.unwrap() > 1000.0));
     }
 }
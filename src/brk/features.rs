@@ -0,0 +1,208 @@
+//! Incremental extraction of the features of a GeoJSON FeatureCollection.
+//!
+//! The response body is fed in chunks as it arrives, and every feature is handed out as soon
+//! as it is complete, so only the feature that is currently being received is kept in memory.
+
+/// Where the splitter is in the FeatureCollection
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    /// Before the `features` array, e.g. the `type` and `crs` members
+    Preamble,
+    /// Inside the `features` array
+    Features,
+    /// After the `features` array
+    Done,
+}
+
+/// Splits a FeatureCollection into the JSON of its individual features.
+pub(super) struct FeatureSplitter {
+    buffer: Vec<u8>,
+    /// The position up to which the buffer has been scanned
+    pos: usize,
+    phase: Phase,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    string_start: usize,
+    /// The last member name encountered in the top-level object
+    key: Vec<u8>,
+    /// The start of the feature that is currently being received
+    feature_start: Option<usize>,
+}
+
+impl FeatureSplitter {
+    pub(super) fn new() -> Self {
+        FeatureSplitter {
+            buffer: Vec::new(),
+            pos: 0,
+            phase: Phase::Preamble,
+            depth: 0,
+            in_string: false,
+            escaped: false,
+            string_start: 0,
+            key: Vec::new(),
+            feature_start: None,
+        }
+    }
+
+    /// Whether the end of the `features` array has been seen.
+    #[cfg(test)]
+    fn is_done(&self) -> bool {
+        self.phase == Phase::Done
+    }
+
+    /// Feed the next chunk of the body, returning the features that were completed by it.
+    pub(super) fn push(&mut self, chunk: &[u8]) -> Vec<Vec<u8>> {
+        let mut features = Vec::new();
+
+        if self.phase == Phase::Done {
+            return features;
+        }
+
+        self.buffer.extend_from_slice(chunk);
+
+        while self.pos < self.buffer.len() {
+            let byte = self.buffer[self.pos];
+
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if byte == b'\\' {
+                    self.escaped = true;
+                } else if byte == b'"' {
+                    self.in_string = false;
+                    if self.phase == Phase::Preamble && self.depth == 1 {
+                        self.key = self.buffer[self.string_start..self.pos].to_vec();
+                    }
+                }
+                self.pos += 1;
+                continue;
+            }
+
+            match byte {
+                b'"' => {
+                    self.in_string = true;
+                    self.string_start = self.pos + 1;
+                }
+                b'{' | b'[' => {
+                    if self.phase == Phase::Features && self.depth == 2 && byte == b'{' {
+                        self.feature_start = Some(self.pos);
+                    } else if self.phase == Phase::Preamble
+                        && self.depth == 1
+                        && byte == b'['
+                        && self.key == b"features"
+                    {
+                        self.phase = Phase::Features;
+                    }
+                    self.depth += 1;
+                }
+                b'}' | b']' => {
+                    self.depth = self.depth.saturating_sub(1);
+                    if self.phase == Phase::Features {
+                        if self.depth == 2 {
+                            if let Some(start) = self.feature_start.take() {
+                                features.push(self.buffer[start..=self.pos].to_vec());
+                            }
+                        } else if self.depth == 1 {
+                            self.phase = Phase::Done;
+                        }
+                    }
+                }
+                b',' if self.depth == 1 => self.key.clear(),
+                _ => {}
+            }
+
+            self.pos += 1;
+        }
+
+        self.compact();
+
+        features
+    }
+
+    /// Drop the part of the buffer that is no longer needed
+    fn compact(&mut self) {
+        let keep_from = match self.phase {
+            // The name of a member may still be incomplete
+            Phase::Preamble if self.in_string => self.string_start.saturating_sub(1),
+            Phase::Preamble => self.pos,
+            Phase::Features => self.feature_start.unwrap_or(self.pos),
+            Phase::Done => self.buffer.len(),
+        };
+
+        self.buffer.drain(..keep_from);
+        self.pos -= keep_from;
+        self.string_start = self.string_start.saturating_sub(keep_from);
+        if let Some(start) = &mut self.feature_start {
+            *start -= keep_from;
+        }
+    }
+
+    /// The number of bytes currently held back, for testing that memory stays bounded.
+    #[cfg(test)]
+    fn buffered(&self) -> usize {
+        self.buffer.len()
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    const COLLECTION: &str = r#"{
+        "type": "FeatureCollection",
+        "name": "features",
+        "crs": {"type": "name", "properties": {"name": "urn:ogc:def:crs:EPSG::28992"}},
+        "features": [
+            {"type": "Feature", "id": "perceel.1", "properties": {"naam": "a \"}\" b"}, "geometry": null},
+            {"type": "Feature", "id": "perceel.2", "properties": {"list": [1, 2, {"x": []}]}, "geometry": null}
+        ],
+        "numberReturned": 2
+    }"#;
+
+    fn ids(features: &[Vec<u8>]) -> Vec<String> {
+        features
+            .iter()
+            .map(|feature| {
+                let value: serde_json::Value = serde_json::from_slice(feature).unwrap();
+                value["id"].as_str().unwrap().to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_split_whole() {
+        let mut splitter = FeatureSplitter::new();
+        let features = splitter.push(COLLECTION.as_bytes());
+
+        assert_eq!(ids(&features), ["perceel.1", "perceel.2"]);
+        assert!(splitter.is_done());
+    }
+
+    #[test]
+    fn test_split_chunks() {
+        for chunk_size in 1..20 {
+            let mut splitter = FeatureSplitter::new();
+            let mut features = Vec::new();
+
+            for chunk in COLLECTION.as_bytes().chunks(chunk_size) {
+                features.extend(splitter.push(chunk));
+                // At most the feature that is being received is buffered
+                assert!(splitter.buffered() < 120 + chunk_size);
+            }
+
+            assert_eq!(ids(&features), ["perceel.1", "perceel.2"]);
+            assert!(splitter.is_done());
+        }
+    }
+
+    #[test]
+    fn test_split_empty() {
+        let mut splitter = FeatureSplitter::new();
+        let features = splitter.push(br#"{"type": "FeatureCollection", "features": []}"#);
+
+        assert!(features.is_empty());
+        assert!(splitter.is_done());
+    }
+}
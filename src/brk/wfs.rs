@@ -0,0 +1,373 @@
+//! OGC filter encoding for WFS GetFeature requests.
+//!
+//! ```
+//! use pdok_apis::brk::wfs::Filter;
+//!
+//! let filter = Filter::and(vec![
+//!     Filter::property_eq("sectie", "M"),
+//!     Filter::property_eq("perceelnummer", "5038"),
+//! ]);
+//!
+//! assert!(filter.to_xml().contains("<Literal>5038</Literal>"));
+//! ```
+
+use std::fmt::Write;
+
+use geo::{Coord, Geometry, LineString, Polygon, Rect};
+
+use crate::CoordinateSpace;
+
+/// A filter on the properties of features, rendered as OGC filter XML
+#[derive(Clone, Debug, PartialEq)]
+pub enum Filter {
+    PropertyIsEqualTo {
+        property: String,
+        literal: String,
+    },
+    PropertyIsLessThanOrEqualTo {
+        property: String,
+        literal: String,
+    },
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+    /// The geometry property intersects the bounding box
+    Bbox {
+        property: String,
+        bbox: Rect<f64>,
+        crs: CoordinateSpace,
+    },
+    /// The geometry property intersects the geometry
+    Intersects {
+        property: String,
+        geometry: Geometry<f64>,
+        crs: CoordinateSpace,
+    },
+    /// The geometry property lies within `distance` meters of the geometry
+    DWithin {
+        property: String,
+        geometry: Geometry<f64>,
+        distance: f64,
+        crs: CoordinateSpace,
+    },
+}
+
+impl Filter {
+    pub fn property_eq(property: &str, literal: impl ToString) -> Self {
+        Filter::PropertyIsEqualTo {
+            property: property.to_string(),
+            literal: literal.to_string(),
+        }
+    }
+
+    pub fn property_le(property: &str, literal: impl ToString) -> Self {
+        Filter::PropertyIsLessThanOrEqualTo {
+            property: property.to_string(),
+            literal: literal.to_string(),
+        }
+    }
+
+    pub fn and(filters: Vec<Filter>) -> Self {
+        Filter::And(filters)
+    }
+
+    pub fn or(filters: Vec<Filter>) -> Self {
+        Filter::Or(filters)
+    }
+
+    /// Coordinates are in the given coordinate space, with longitude in x for GPS.
+    pub fn bbox(property: &str, bbox: Rect<f64>, crs: CoordinateSpace) -> Self {
+        Filter::Bbox {
+            property: property.to_string(),
+            bbox,
+            crs,
+        }
+    }
+
+    /// Coordinates are in the given coordinate space, with longitude in x for GPS.
+    pub fn intersects(property: &str, geometry: Geometry<f64>, crs: CoordinateSpace) -> Self {
+        Filter::Intersects {
+            property: property.to_string(),
+            geometry,
+            crs,
+        }
+    }
+
+    /// Coordinates are in the given coordinate space, with longitude in x for GPS.
+    /// The distance is always in meters.
+    pub fn dwithin(
+        property: &str,
+        geometry: Geometry<f64>,
+        distance: f64,
+        crs: CoordinateSpace,
+    ) -> Self {
+        Filter::DWithin {
+            property: property.to_string(),
+            geometry,
+            distance,
+            crs,
+        }
+    }
+
+    /// Render the filter as a `<Filter>` document
+    pub fn to_xml(&self) -> String {
+        let mut xml = String::from(r#"<Filter xmlns:gml="http://www.opengis.net/gml/3.2">"#);
+        self.write_xml(&mut xml);
+        xml.push_str("</Filter>");
+        xml
+    }
+
+    fn write_xml(&self, xml: &mut String) {
+        match self {
+            Filter::PropertyIsEqualTo { property, literal } => {
+                write_comparison(xml, "PropertyIsEqualTo", property, literal)
+            }
+            Filter::PropertyIsLessThanOrEqualTo { property, literal } => {
+                write_comparison(xml, "PropertyIsLessThanOrEqualTo", property, literal)
+            }
+            Filter::And(filters) => write_logical(xml, "And", filters),
+            Filter::Or(filters) => write_logical(xml, "Or", filters),
+            Filter::Bbox {
+                property,
+                bbox,
+                crs,
+            } => {
+                xml.push_str("<BBOX>");
+                write_property(xml, property);
+                let _ = write!(xml, r#"<gml:Envelope srsName="{}">"#, crs.srs_name());
+                let _ = write!(
+                    xml,
+                    "<gml:lowerCorner>{}</gml:lowerCorner><gml:upperCorner>{}</gml:upperCorner>",
+                    pos(bbox.min(), *crs),
+                    pos(bbox.max(), *crs)
+                );
+                xml.push_str("</gml:Envelope></BBOX>");
+            }
+            Filter::Intersects {
+                property,
+                geometry,
+                crs,
+            } => {
+                xml.push_str("<Intersects>");
+                write_property(xml, property);
+                write_gml(xml, geometry, *crs, true);
+                xml.push_str("</Intersects>");
+            }
+            Filter::DWithin {
+                property,
+                geometry,
+                distance,
+                crs,
+            } => {
+                xml.push_str("<DWithin>");
+                write_property(xml, property);
+                write_gml(xml, geometry, *crs, true);
+                let _ = write!(xml, r#"<Distance uom="m">{distance}</Distance>"#);
+                xml.push_str("</DWithin>");
+            }
+        }
+    }
+}
+
+impl From<Filter> for super::WfsFilter {
+    fn from(filter: Filter) -> Self {
+        super::WfsFilter::Xml(filter.to_xml())
+    }
+}
+
+/// Escape the characters that are not allowed in XML text and attribute values
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+fn write_property(xml: &mut String, property: &str) {
+    let _ = write!(xml, "<PropertyName>{}</PropertyName>", escape(property));
+}
+
+fn write_comparison(xml: &mut String, operator: &str, property: &str, literal: &str) {
+    let _ = write!(xml, "<{operator}>");
+    write_property(xml, property);
+    let _ = write!(xml, "<Literal>{}</Literal></{operator}>", escape(literal));
+}
+
+fn write_logical(xml: &mut String, operator: &str, filters: &[Filter]) {
+    let _ = write!(xml, "<{operator}>");
+    for filter in filters {
+        filter.write_xml(xml);
+    }
+    let _ = write!(xml, "</{operator}>");
+}
+
+/// A position, in latitude, longitude order for GPS as required by its urn notation
+fn pos(coord: Coord<f64>, crs: CoordinateSpace) -> String {
+    match crs {
+        CoordinateSpace::Rijksdriehoek => format!("{} {}", coord.x, coord.y),
+        CoordinateSpace::Gps => format!("{} {}", coord.y, coord.x),
+    }
+}
+
+fn pos_list(line: &LineString<f64>, crs: CoordinateSpace) -> String {
+    line.coords()
+        .map(|&coord| pos(coord, crs))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Write a polygon, `srs_name` being either empty or a complete `srsName` attribute
+fn write_polygon_gml(
+    xml: &mut String,
+    polygon: &Polygon<f64>,
+    crs: CoordinateSpace,
+    srs_name: &str,
+) {
+    let _ = write!(
+        xml,
+        "<gml:Polygon{srs_name}><gml:exterior><gml:LinearRing><gml:posList>{}</gml:posList></gml:LinearRing></gml:exterior>",
+        pos_list(polygon.exterior(), crs)
+    );
+    for interior in polygon.interiors() {
+        let _ = write!(
+            xml,
+            "<gml:interior><gml:LinearRing><gml:posList>{}</gml:posList></gml:LinearRing></gml:interior>",
+            pos_list(interior, crs)
+        );
+    }
+    xml.push_str("</gml:Polygon>");
+}
+
+/// Write a geometry as GML 3.2, only the outermost element carries the `srsName`
+fn write_gml(xml: &mut String, geometry: &Geometry<f64>, crs: CoordinateSpace, root: bool) {
+    let srs_name = if root {
+        format!(r#" srsName="{}""#, crs.srs_name())
+    } else {
+        String::new()
+    };
+
+    match geometry {
+        Geometry::Point(point) => {
+            let _ = write!(
+                xml,
+                "<gml:Point{srs_name}><gml:pos>{}</gml:pos></gml:Point>",
+                pos(point.0, crs)
+            );
+        }
+        Geometry::Line(line) => {
+            write_gml(xml, &Geometry::LineString(line.into()), crs, root);
+        }
+        Geometry::LineString(line) => {
+            let _ = write!(
+                xml,
+                "<gml:LineString{srs_name}><gml:posList>{}</gml:posList></gml:LineString>",
+                pos_list(line, crs)
+            );
+        }
+        Geometry::Polygon(polygon) => write_polygon_gml(xml, polygon, crs, &srs_name),
+        Geometry::Rect(rect) => write_polygon_gml(xml, &rect.to_polygon(), crs, &srs_name),
+        Geometry::Triangle(triangle) => {
+            write_polygon_gml(xml, &triangle.to_polygon(), crs, &srs_name)
+        }
+        Geometry::MultiPoint(points) => {
+            let _ = write!(xml, "<gml:MultiPoint{srs_name}>");
+            for point in points {
+                xml.push_str("<gml:pointMember>");
+                write_gml(xml, &Geometry::Point(*point), crs, false);
+                xml.push_str("</gml:pointMember>");
+            }
+            xml.push_str("</gml:MultiPoint>");
+        }
+        Geometry::MultiLineString(lines) => {
+            let _ = write!(xml, "<gml:MultiCurve{srs_name}>");
+            for line in lines {
+                xml.push_str("<gml:curveMember>");
+                write_gml(xml, &Geometry::LineString(line.clone()), crs, false);
+                xml.push_str("</gml:curveMember>");
+            }
+            xml.push_str("</gml:MultiCurve>");
+        }
+        Geometry::MultiPolygon(polygons) => {
+            let _ = write!(xml, "<gml:MultiSurface{srs_name}>");
+            for polygon in polygons {
+                xml.push_str("<gml:surfaceMember>");
+                write_polygon_gml(xml, polygon, crs, "");
+                xml.push_str("</gml:surfaceMember>");
+            }
+            xml.push_str("</gml:MultiSurface>");
+        }
+        Geometry::GeometryCollection(geometries) => {
+            let _ = write!(xml, "<gml:MultiGeometry{srs_name}>");
+            for geometry in geometries {
+                xml.push_str("<gml:geometryMember>");
+                write_gml(xml, geometry, crs, false);
+                xml.push_str("</gml:geometryMember>");
+            }
+            xml.push_str("</gml:MultiGeometry>");
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_compound_filter() {
+        let filter = Filter::and(vec![
+            Filter::or(vec![
+                Filter::property_eq("sectie", "M"),
+                Filter::property_eq("sectie", "<N & 'O'>"),
+            ]),
+            Filter::property_le("beginGeldigheid", "2020-01-01"),
+        ]);
+
+        assert_eq!(
+            filter.to_xml(),
+            concat!(
+                r#"<Filter xmlns:gml="http://www.opengis.net/gml/3.2"><And><Or>"#,
+                "<PropertyIsEqualTo><PropertyName>sectie</PropertyName><Literal>M</Literal></PropertyIsEqualTo>",
+                "<PropertyIsEqualTo><PropertyName>sectie</PropertyName><Literal>&lt;N &amp; &apos;O&apos;&gt;</Literal></PropertyIsEqualTo>",
+                "</Or><PropertyIsLessThanOrEqualTo><PropertyName>beginGeldigheid</PropertyName><Literal>2020-01-01</Literal></PropertyIsLessThanOrEqualTo>",
+                "</And></Filter>"
+            )
+        );
+    }
+
+    #[test]
+    fn test_spatial_filters() {
+        let bbox = Rect::new(Coord { x: 5.0, y: 51.0 }, Coord { x: 6.0, y: 52.0 });
+        let xml = Filter::bbox("begrenzingPerceel", bbox, CoordinateSpace::Gps).to_xml();
+        assert!(xml.contains(concat!(
+            r#"<BBOX><PropertyName>begrenzingPerceel</PropertyName><gml:Envelope srsName="urn:ogc:def:crs:EPSG::4258">"#,
+            "<gml:lowerCorner>51 5</gml:lowerCorner><gml:upperCorner>52 6</gml:upperCorner></gml:Envelope></BBOX>"
+        )));
+
+        let point = Geometry::Point(geo::Point::new(187_000.0, 428_000.0));
+        let xml = Filter::dwithin(
+            "begrenzingPerceel",
+            point,
+            5.0,
+            CoordinateSpace::Rijksdriehoek,
+        )
+        .to_xml();
+        assert!(xml.contains(concat!(
+            r#"<DWithin><PropertyName>begrenzingPerceel</PropertyName><gml:Point srsName="urn:ogc:def:crs:EPSG::28992">"#,
+            r#"<gml:pos>187000 428000</gml:pos></gml:Point><Distance uom="m">5</Distance></DWithin>"#
+        )));
+
+        let square = Geometry::Polygon(bbox.to_polygon());
+        let xml = Filter::intersects("begrenzingPerceel", square, CoordinateSpace::Gps).to_xml();
+        assert!(xml.contains("<gml:posList>51 6 52 6 52 5 51 5 51 6</gml:posList>"));
+    }
+}
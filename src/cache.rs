@@ -0,0 +1,79 @@
+//! In-memory cache of successful responses, keyed by request url.
+
+use std::{
+    num::NonZeroUsize,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use bytes::Bytes;
+use lru::LruCache;
+use reqwest::{header::HeaderMap, Response, StatusCode};
+
+pub(crate) struct ResponseCache {
+    entries: Mutex<LruCache<String, CachedResponse>>,
+    ttl: Option<Duration>,
+}
+
+#[derive(Clone)]
+struct CachedResponse {
+    stored_at: Instant,
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+}
+
+impl CachedResponse {
+    fn to_response(&self) -> Response {
+        let mut response = http::Response::new(self.body.clone());
+        *response.status_mut() = self.status;
+        *response.headers_mut() = self.headers.clone();
+
+        Response::from(response)
+    }
+}
+
+impl ResponseCache {
+    pub(crate) fn new(capacity: NonZeroUsize, ttl: Option<Duration>) -> Self {
+        ResponseCache {
+            entries: Mutex::new(LruCache::new(capacity)),
+            ttl,
+        }
+    }
+
+    /// Look up a response, discarding it when it has expired
+    pub(crate) fn get(&self, url: &str) -> Option<Response> {
+        let mut entries = self.entries.lock().unwrap();
+        let entry = entries.get(url)?;
+
+        if self.ttl.is_some_and(|ttl| entry.stored_at.elapsed() > ttl) {
+            entries.pop(url);
+            return None;
+        }
+
+        Some(entry.to_response())
+    }
+
+    /// Read the body of a response and store it, returning an equivalent response
+    pub(crate) async fn store(
+        &self,
+        url: String,
+        response: Response,
+    ) -> Result<Response, reqwest::Error> {
+        let status = response.status();
+        let headers = response.headers().clone();
+        let body = response.bytes().await?;
+
+        let entry = CachedResponse {
+            stored_at: Instant::now(),
+            status,
+            headers,
+            body,
+        };
+        let response = entry.to_response();
+
+        self.entries.lock().unwrap().put(url, entry);
+
+        Ok(response)
+    }
+}
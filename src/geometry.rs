@@ -0,0 +1,270 @@
+//! Conversions from the GeoJSON geometries returned by PDOK to `geo` types.
+//!
+//! Positions may carry a third (z) coordinate, which is ignored.
+
+use geo::{Coord, LineString, MultiPolygon, Polygon};
+use geojson::{Position, Value};
+
+/// A GeoJSON geometry could not be converted
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeometryError {
+    /// A position did not consist of 2 or 3 coordinates
+    InvalidPosition(Position),
+    /// A polygon did not have an exterior ring
+    MissingExterior,
+    /// The geometry was of another type than expected, e.g. a point instead of a polygon
+    UnexpectedType(&'static str),
+    /// A WKT geometry could not be parsed
+    InvalidWkt(String),
+}
+
+/// Convert a single position, ignoring the z coordinate if present
+fn coord(position: &Position) -> Result<Coord<f64>, GeometryError> {
+    match position[..] {
+        [x, y] | [x, y, _] => Ok(Coord { x, y }),
+        _ => Err(GeometryError::InvalidPosition(position.clone())),
+    }
+}
+
+fn linestring_help(positions: &[Position]) -> Result<LineString<f64>, GeometryError> {
+    positions.iter().map(coord).collect()
+}
+
+/// Convert the rings of a polygon, the first of which is the exterior
+fn polygon_help(rings: &[Vec<Position>]) -> Result<Polygon<f64>, GeometryError> {
+    let (outer_positions, inner_positions) =
+        rings.split_first().ok_or(GeometryError::MissingExterior)?;
+
+    let outer = linestring_help(outer_positions)?;
+    let inners = inner_positions
+        .iter()
+        .map(|positions| linestring_help(positions))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(Polygon::new(outer, inners))
+}
+
+/// Convert a GeoJSON polygon.
+pub fn geojson_value_to_polygon(value: &Value) -> Result<Polygon<f64>, GeometryError> {
+    match value {
+        Value::Polygon(rings) => polygon_help(rings),
+        other => Err(GeometryError::UnexpectedType(other.type_name())),
+    }
+}
+
+/// Convert a GeoJSON polygon or multipolygon, a polygon yielding a single element.
+pub fn geojson_value_to_multipolygon(value: &Value) -> Result<MultiPolygon<f64>, GeometryError> {
+    match value {
+        Value::Polygon(rings) => Ok(MultiPolygon::new(vec![polygon_help(rings)?])),
+        Value::MultiPolygon(polygons) => polygons
+            .iter()
+            .map(|rings| polygon_help(rings))
+            .collect::<Result<Vec<_>, _>>()
+            .map(MultiPolygon::new),
+        other => Err(GeometryError::UnexpectedType(other.type_name())),
+    }
+}
+
+/// Convert a GeoJSON geometry of any type.
+pub fn geojson_geometry_to_geo(
+    geometry: &geojson::Geometry,
+) -> Result<geo::Geometry<f64>, GeometryError> {
+    value_to_geo(&geometry.value)
+}
+
+/// Format a GeoJSON geometry as WKT, e.g. for `ST_GeomFromText` in PostGIS.
+pub fn geojson_geometry_to_wkt(geometry: &geojson::Geometry) -> Result<String, GeometryError> {
+    use wkt::ToWkt;
+
+    Ok(geojson_geometry_to_geo(geometry)?.wkt_string())
+}
+
+/// Parse a WKT geometry, as returned by the locatieserver.
+pub fn wkt_to_geo(wkt: &str) -> Result<geo::Geometry<f64>, GeometryError> {
+    use wkt::TryFromWkt;
+
+    geo::Geometry::try_from_wkt_str(wkt).map_err(|e| GeometryError::InvalidWkt(e.to_string()))
+}
+
+/// Parse a WKT point, such as the centroids returned by the locatieserver.
+pub fn wkt_to_point(wkt: &str) -> Result<geo::Point<f64>, GeometryError> {
+    match wkt_to_geo(wkt)? {
+        geo::Geometry::Point(point) => Ok(point),
+        other => Err(GeometryError::UnexpectedType(
+            Value::from(&other).type_name(),
+        )),
+    }
+}
+
+/// A feature with the given geometry, and the other serialized fields of `value` as
+/// its properties
+pub(crate) fn to_feature<T: serde::Serialize>(
+    value: &T,
+    id: &str,
+    geometry: &geojson::Geometry,
+) -> geojson::Feature {
+    let properties = match serde_json::to_value(value) {
+        Ok(serde_json::Value::Object(mut properties)) => {
+            properties.remove("geometry");
+            Some(properties)
+        }
+        _ => None,
+    };
+
+    geojson::Feature {
+        bbox: None,
+        geometry: Some(geometry.clone()),
+        id: Some(geojson::feature::Id::String(id.to_string())),
+        properties,
+        foreign_members: None,
+    }
+}
+
+fn value_to_geo(value: &Value) -> Result<geo::Geometry<f64>, GeometryError> {
+    let geometry = match value {
+        Value::Point(position) => geo::Point::from(coord(position)?).into(),
+        Value::MultiPoint(positions) => positions
+            .iter()
+            .map(|position| coord(position).map(geo::Point::from))
+            .collect::<Result<Vec<_>, _>>()
+            .map(geo::MultiPoint::new)?
+            .into(),
+        Value::LineString(positions) => linestring_help(positions)?.into(),
+        Value::MultiLineString(lines) => lines
+            .iter()
+            .map(|positions| linestring_help(positions))
+            .collect::<Result<Vec<_>, _>>()
+            .map(geo::MultiLineString::new)?
+            .into(),
+        Value::Polygon(rings) => polygon_help(rings)?.into(),
+        Value::MultiPolygon(_) => geojson_value_to_multipolygon(value)?.into(),
+        Value::GeometryCollection(geometries) => geometries
+            .iter()
+            .map(geojson_geometry_to_geo)
+            .collect::<Result<Vec<_>, _>>()
+            .map(|geometries| geo::Geometry::GeometryCollection(geometries.into()))?,
+    };
+
+    Ok(geometry)
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    fn square(z: bool) -> Vec<Vec<Position>> {
+        let corners = [
+            [0.0, 0.0],
+            [10.0, 0.0],
+            [10.0, 10.0],
+            [0.0, 10.0],
+            [0.0, 0.0],
+        ];
+
+        vec![corners
+            .iter()
+            .map(|&[x, y]| if z { vec![x, y, 3.5] } else { vec![x, y] })
+            .collect()]
+    }
+
+    #[test]
+    fn test_geojson_value_to_polygon() {
+        use geo::algorithm::area::Area;
+
+        let polygon = geojson_value_to_polygon(&Value::Polygon(square(false))).unwrap();
+        assert_eq!(polygon.unsigned_area(), 100.0);
+
+        let polygon_z = geojson_value_to_polygon(&Value::Polygon(square(true))).unwrap();
+        assert_eq!(polygon, polygon_z);
+
+        assert_eq!(
+            geojson_value_to_polygon(&Value::Point(vec![0.0, 0.0])),
+            Err(GeometryError::UnexpectedType("Point"))
+        );
+        assert_eq!(
+            geojson_value_to_polygon(&Value::Polygon(vec![])),
+            Err(GeometryError::MissingExterior)
+        );
+        assert_eq!(
+            geojson_value_to_polygon(&Value::Polygon(vec![vec![vec![0.0]]])),
+            Err(GeometryError::InvalidPosition(vec![0.0]))
+        );
+    }
+
+    #[test]
+    fn test_geojson_value_to_multipolygon() {
+        let single = geojson_value_to_multipolygon(&Value::Polygon(square(true))).unwrap();
+        assert_eq!(single.0.len(), 1);
+
+        let multi =
+            geojson_value_to_multipolygon(&Value::MultiPolygon(vec![square(true), square(false)]))
+                .unwrap();
+        assert_eq!(multi.0.len(), 2);
+        assert_eq!(multi.0[0], multi.0[1]);
+    }
+
+    #[test]
+    fn test_wkt_to_geo() {
+        assert_eq!(
+            wkt_to_geo("POINT(5.85 51.84)"),
+            Ok(geo::Geometry::Point(geo::Point::new(5.85, 51.84)))
+        );
+        assert!(matches!(
+            wkt_to_geo("POLYGON((0 0,10 0,10 10,0 0))"),
+            Ok(geo::Geometry::Polygon(_))
+        ));
+        assert!(matches!(
+            wkt_to_geo("POLYGON((0 0"),
+            Err(GeometryError::InvalidWkt(_))
+        ));
+        assert_eq!(
+            wkt_to_point("POLYGON((0 0,10 0,10 10,0 0))"),
+            Err(GeometryError::UnexpectedType("Polygon"))
+        );
+    }
+
+    #[test]
+    fn test_geojson_geometry_to_wkt() {
+        let point = geojson::Geometry::new(Value::Point(vec![5.85, 51.84]));
+        assert_eq!(
+            geojson_geometry_to_wkt(&point),
+            Ok("POINT(5.85 51.84)".to_string())
+        );
+
+        let malformed = geojson::Geometry::new(Value::Polygon(vec![vec![
+            vec![0.0, 0.0],
+            vec![10.0],
+            vec![10.0, 10.0],
+            vec![0.0, 0.0],
+        ]]));
+        assert_eq!(
+            geojson_geometry_to_wkt(&malformed),
+            Err(GeometryError::InvalidPosition(vec![10.0]))
+        );
+    }
+
+    #[test]
+    fn test_geojson_geometry_to_geo() {
+        let point = geojson::Geometry::new(Value::Point(vec![5.0, 52.0, 12.0]));
+        assert_eq!(
+            geojson_geometry_to_geo(&point),
+            Ok(geo::Geometry::Point(geo::Point::new(5.0, 52.0)))
+        );
+
+        let collection = geojson::Geometry::new(Value::GeometryCollection(vec![
+            point,
+            geojson::Geometry::new(Value::Polygon(square(true))),
+        ]));
+        assert!(matches!(
+            geojson_geometry_to_geo(&collection),
+            Ok(geo::Geometry::GeometryCollection(collection)) if collection.0.len() == 2
+        ));
+
+        let malformed = geojson::Geometry::new(Value::LineString(vec![vec![1.0, 2.0], vec![]]));
+        assert_eq!(
+            geojson_geometry_to_geo(&malformed),
+            Err(GeometryError::InvalidPosition(vec![]))
+        );
+    }
+}
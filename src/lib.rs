@@ -4,29 +4,98 @@
 //! See [the service documentation](https://www.pdok.nl/introductie/-/article/pdok-locatieserver)
 //! for more information on its capabilities.
 
+#[cfg(all(feature = "cache", target_arch = "wasm32"))]
+compile_error!("the `cache` feature is not supported on wasm");
+
 pub mod bag;
 pub mod brk;
+#[cfg(feature = "cache")]
+mod cache;
+pub mod geometry;
 pub mod lookup;
+pub mod pdok;
+#[cfg(feature = "testkit")]
+pub mod testkit;
+mod transport;
 pub mod util;
 
 #[derive(Debug)]
 pub enum Error {
     /// Something went wrong with the request (invalid url, no connection, etc)
     NetworkProblem(reqwest::Error),
+    /// The request did not complete within the configured timeout
+    Timeout(reqwest::Error),
     /// Data was received, but could not be decoded
     JsonProblem(reqwest::Error),
+    /// A JSON body was received, but did not match the expected structure.
+    /// Contains the start of the body, e.g. to find a field that was renamed.
+    DecodeError {
+        source: serde_json::Error,
+        body_snippet: String,
+    },
     /// Data was decoded, but no items were found
     EmptyResponse,
+    /// A custom header name or value was not valid
+    InvalidHeader(String),
+    /// Data was received, but could not be parsed as XML
+    XmlProblem(roxmltree::Error),
+    /// The service responded with an error status (4xx or 5xx)
+    HttpStatus { code: u16, body: String },
+    /// A geometry was received, but could not be converted
+    GeometryError(geometry::GeometryError),
+    /// More locations matched than the configured maximum, the query should be narrowed
+    TooManyResults { num_found: usize, cap: usize },
+    /// A geometry failed validation, see e.g. [`bag::BagClientBuilder::validate_geometry`]
+    InvalidGeometry {
+        /// The identification of the pand or lot
+        id: String,
+        error: util::GeometryValidationError,
+    },
+    /// The environment variable with the given name is not set (or not valid unicode)
+    MissingEnvVar(String),
+    /// The API key of a client is empty, see [`bag::BagClientBuilder::try_build`]
+    MissingApiKey,
+    /// The service is throttling requests (429 Too Many Requests) and asks to wait before
+    /// the next one, see [`ClientBuilder::rate_limit_retries`]
+    RateLimited { retry_after: std::time::Duration },
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            Error::Timeout(error)
+        } else if error.is_decode() {
+            Error::JsonProblem(error)
+        } else if let Some(status) = error.status() {
+            Error::HttpStatus {
+                code: status.as_u16(),
+                body: String::new(),
+            }
+        } else {
+            Error::NetworkProblem(error)
+        }
+    }
 }
 
 /// Supported coordinate spaces
-#[derive(Copy, Clone)]
+///
+/// (De)serializes as its EPSG code, e.g. `"epsg:28992"`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum CoordinateSpace {
     Rijksdriehoek,
     Gps,
 }
 
 impl CoordinateSpace {
+    /// Parse an EPSG code, e.g. `"epsg:28992"`
+    pub fn from_epsg(epsg: &str) -> Option<Self> {
+        match epsg.to_ascii_lowercase().as_str() {
+            "epsg:28992" => Some(CoordinateSpace::Rijksdriehoek),
+            "epsg:4258" => Some(CoordinateSpace::Gps),
+            _ => None,
+        }
+    }
+
     fn as_str(&self) -> &'static str {
         match self {
             CoordinateSpace::Rijksdriehoek => {
@@ -39,11 +108,208 @@ impl CoordinateSpace {
             }
         }
     }
+
+    /// The OGC urn of the coordinate space, as used in WFS requests
+    fn srs_name(&self) -> &'static str {
+        match self {
+            CoordinateSpace::Rijksdriehoek => "urn:ogc:def:crs:EPSG::28992",
+            CoordinateSpace::Gps => "urn:ogc:def:crs:EPSG::4258",
+        }
+    }
+}
+
+/// Parse a custom header, for use in [`ClientBuilder::header`]
+fn parse_header(
+    name: &str,
+    value: &str,
+) -> Result<(reqwest::header::HeaderName, reqwest::header::HeaderValue), Error> {
+    use reqwest::header::{HeaderName, HeaderValue};
+
+    let header_name =
+        HeaderName::from_bytes(name.as_bytes()).map_err(|_| Error::InvalidHeader(name.into()))?;
+    let header_value =
+        HeaderValue::from_str(value).map_err(|_| Error::InvalidHeader(name.into()))?;
+
+    Ok((header_name, header_value))
+}
+
+impl serde::Serialize for CoordinateSpace {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for CoordinateSpace {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let epsg = String::deserialize(deserializer)?;
+        CoordinateSpace::from_epsg(&epsg)
+            .ok_or_else(|| serde::de::Error::custom(format!("unsupported crs: {epsg}")))
+    }
+}
+
+/// The HTTP status and headers of a response, returned by the `_with_meta` variants of
+/// queries, e.g. [`lookup::LookupClient::lookup_with_meta`].
+#[derive(Debug, Clone)]
+pub struct ResponseMeta {
+    pub status: u16,
+    pub headers: reqwest::header::HeaderMap,
+}
+
+impl ResponseMeta {
+    fn from_response(response: &reqwest::Response) -> Self {
+        ResponseMeta {
+            status: response.status().as_u16(),
+            headers: response.headers().clone(),
+        }
+    }
+
+    /// The value of a header, if present and valid text.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers.get(name)?.to_str().ok()
+    }
+
+    /// The number of requests left in the current rate limit window, from the
+    /// `X-RateLimit-Remaining` header.
+    pub fn rate_limit_remaining(&self) -> Option<u64> {
+        self.header("X-RateLimit-Remaining")?.trim().parse().ok()
+    }
 }
 
+/// The user agent sent when none is configured on the builder
+pub const DEFAULT_USER_AGENT: &str = concat!("pdok-apis/", env!("CARGO_PKG_VERSION"));
+
 pub trait ClientBuilder<'a> {
     type OutputType;
+    /// Identify the application with a custom user agent, which is followed by
+    /// [`DEFAULT_USER_AGENT`], e.g. `myapp/1.2 pdok-apis/0.2.0`
+    fn user_agent(&mut self, user_agent: &'a str) -> &mut Self;
+    /// Whether to follow a custom user agent by [`DEFAULT_USER_AGENT`] (the default),
+    /// or send it as is
+    fn user_agent_suffix(&mut self, user_agent_suffix: bool) -> &mut Self;
+    /// Time to wait for a connection to be established. Ignored on wasm.
     fn connection_timeout_secs(&mut self, connection_timeout_secs: u64) -> &mut Self;
+    /// Total time a request may take, from connecting until the body has been read.
+    /// A timeout of zero disables the timeout altogether. Ignored on wasm.
     fn request_timeout_secs(&mut self, request_timeout_secs: u64) -> &mut Self;
+    /// Follow redirects (the default, up to 10 in a row), or fail with an
+    /// [`Error::HttpStatus`] carrying the redirect status, e.g. to notice that a service moved.
+    /// Ignored on wasm, where the browser always follows redirects.
+    fn follow_redirects(&mut self, follow_redirects: bool) -> &mut Self;
+    /// Send at most `max_concurrency` requests at a time, shared by all clones of the client,
+    /// e.g. to keep a batch job from opening hundreds of connections. Further requests wait
+    /// until the response headers of an earlier one have been received.
+    /// Zero (the default) leaves the number of requests unbounded.
+    fn max_concurrency(&mut self, max_concurrency: usize) -> &mut Self;
+    /// Retry a request up to `rate_limit_retries` times when the service is throttling
+    /// (429 Too Many Requests), after waiting as long as its `Retry-After` header asks.
+    /// Zero (the default) fails with [`Error::RateLimited`] instead. Ignored on wasm.
+    fn rate_limit_retries(&mut self, rate_limit_retries: u32) -> &mut Self;
+    /// The longest `Retry-After` that is waited out before retrying (60 seconds by default).
+    /// A longer wait fails with [`Error::RateLimited`] right away. Ignored on wasm.
+    fn max_retry_after(&mut self, max_retry_after: std::time::Duration) -> &mut Self;
+    /// Send an additional header with every request
+    fn header(&mut self, name: &str, value: &str) -> Result<&mut Self, Error>;
+    /// Use a different base url for the service, e.g. a gateway or a mock server
+    fn base_url(&mut self, base_url: &'a str) -> &mut Self;
+    /// Keep up to `capacity` successful responses in memory, keyed by request url.
+    /// A capacity of zero (the default) disables the cache.
+    #[cfg(feature = "cache")]
+    fn cache_capacity(&mut self, capacity: usize) -> &mut Self;
+    /// Discard cached responses after the given duration. By default they are kept
+    /// until evicted by newer responses.
+    #[cfg(feature = "cache")]
+    fn cache_ttl(&mut self, ttl: std::time::Duration) -> &mut Self;
     fn build(&self) -> Self::OutputType;
 }
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    macro_rules! aw {
+        ($e:expr) => {
+            tokio_test::block_on($e)
+        };
+    }
+
+    #[test]
+    fn reqwest_error_classification() {
+        use std::time::Duration;
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        let builder = reqwest::Client::new().get("not a url").build().unwrap_err();
+        assert!(matches!(Error::from(builder), Error::NetworkProblem(_)));
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(matchers::path("/invalid"))
+                .respond_with(ResponseTemplate::new(200).set_body_string("not json"))
+                .mount(&server)
+                .await;
+            Mock::given(matchers::path("/slow"))
+                .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+                .mount(&server)
+                .await;
+            Mock::given(matchers::path("/missing"))
+                .respond_with(ResponseTemplate::new(404))
+                .mount(&server)
+                .await;
+
+            let client = reqwest::Client::new();
+            let uri = server.uri();
+
+            let decode = client
+                .get(format!("{uri}/invalid"))
+                .send()
+                .await
+                .unwrap()
+                .json::<serde_json::Value>()
+                .await
+                .unwrap_err();
+            assert!(matches!(Error::from(decode), Error::JsonProblem(_)));
+
+            let timeout = client
+                .get(format!("{uri}/slow"))
+                .timeout(Duration::from_millis(100))
+                .send()
+                .await
+                .unwrap_err();
+            assert!(matches!(Error::from(timeout), Error::Timeout(_)));
+
+            let status = client
+                .get(format!("{uri}/missing"))
+                .send()
+                .await
+                .unwrap()
+                .error_for_status()
+                .unwrap_err();
+            assert!(matches!(
+                Error::from(status),
+                Error::HttpStatus { code: 404, .. }
+            ));
+
+            // Nothing listens on the discard port
+            let connect = client.get("http://127.0.0.1:9").send().await.unwrap_err();
+            assert!(matches!(Error::from(connect), Error::NetworkProblem(_)));
+        });
+    }
+
+    #[test]
+    fn coordinate_space_serde() {
+        for space in [CoordinateSpace::Rijksdriehoek, CoordinateSpace::Gps] {
+            let json = serde_json::to_string(&space).unwrap();
+            assert_eq!(json, format!("\"{}\"", space.as_str()));
+            assert_eq!(
+                serde_json::from_str::<CoordinateSpace>(&json).unwrap(),
+                space
+            );
+        }
+
+        assert_eq!(
+            CoordinateSpace::from_epsg("EPSG:28992"),
+            Some(CoordinateSpace::Rijksdriehoek)
+        );
+        assert!(serde_json::from_str::<CoordinateSpace>("\"epsg:3857\"").is_err());
+    }
+}
@@ -5,21 +5,47 @@
 //! for more information on its capabilities.
 //!
 use crate::{
+    geometry::{wkt_to_geo, wkt_to_point},
+    transport::{self, Transport},
     ClientBuilder,
     Error::{self, *},
+    ResponseMeta,
 };
-use reqwest::Client;
-use serde::{Deserialize, Serialize};
+use futures::Stream;
+use geo::Point;
+use reqwest::header::HeaderMap;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{cmp::Ordering, time::Duration};
 
+/// Client for the locatieserver. Clones share the underlying connection pool.
+#[derive(Clone)]
 pub struct LookupClient {
-    client: Client,
+    client: Transport,
+    base_url: String,
+    version: String,
+    max_results: usize,
+    include_historical: bool,
 }
 
 pub struct LookupClientBuilder<'a> {
+    max_results: usize,
+    include_historical: bool,
+    language: Language,
+    locatieserver_version: &'a str,
     connection_timeout_secs: u64,
     request_timeout_secs: u64,
+    follow_redirects: bool,
+    max_concurrency: usize,
+    rate_limit_retries: u32,
+    max_retry_after: Duration,
     user_agent: &'a str,
+    user_agent_suffix: bool,
+    base_url: &'a str,
+    headers: HeaderMap,
+    #[cfg(feature = "cache")]
+    cache_capacity: usize,
+    #[cfg(feature = "cache")]
+    cache_ttl: Option<Duration>,
 }
 
 impl<'a> ClientBuilder<'a> for LookupClientBuilder<'a> {
@@ -35,30 +61,189 @@ impl<'a> ClientBuilder<'a> for LookupClientBuilder<'a> {
         self
     }
 
+    fn follow_redirects(&mut self, follow_redirects: bool) -> &mut Self {
+        self.follow_redirects = follow_redirects;
+        self
+    }
+
+    fn max_concurrency(&mut self, max_concurrency: usize) -> &mut Self {
+        self.max_concurrency = max_concurrency;
+        self
+    }
+
+    fn rate_limit_retries(&mut self, rate_limit_retries: u32) -> &mut Self {
+        self.rate_limit_retries = rate_limit_retries;
+        self
+    }
+
+    fn max_retry_after(&mut self, max_retry_after: Duration) -> &mut Self {
+        self.max_retry_after = max_retry_after;
+        self
+    }
+
+    fn user_agent(&mut self, user_agent: &'a str) -> &mut Self {
+        self.user_agent = user_agent;
+        self
+    }
+
+    fn user_agent_suffix(&mut self, user_agent_suffix: bool) -> &mut Self {
+        self.user_agent_suffix = user_agent_suffix;
+        self
+    }
+
+    fn header(&mut self, name: &str, value: &str) -> Result<&mut Self, Error> {
+        let (name, value) = crate::parse_header(name, value)?;
+        self.headers.insert(name, value);
+        Ok(self)
+    }
+
+    fn base_url(&mut self, base_url: &'a str) -> &mut Self {
+        self.base_url = base_url;
+        self
+    }
+
+    #[cfg(feature = "cache")]
+    fn cache_capacity(&mut self, capacity: usize) -> &mut Self {
+        self.cache_capacity = capacity;
+        self
+    }
+
+    #[cfg(feature = "cache")]
+    fn cache_ttl(&mut self, ttl: Duration) -> &mut Self {
+        self.cache_ttl = Some(ttl);
+        self
+    }
+
     fn build(&self) -> Self::OutputType {
-        let client = reqwest::ClientBuilder::new()
-            .user_agent(self.user_agent)
-            .connect_timeout(Duration::from_secs(self.connection_timeout_secs))
-            .timeout(Duration::new(self.request_timeout_secs, 0))
-            .build()
-            .unwrap();
+        use reqwest::header::{HeaderValue, ACCEPT_LANGUAGE};
+
+        let mut headers = HeaderMap::new();
+
+        headers.insert(
+            ACCEPT_LANGUAGE,
+            HeaderValue::from_static(self.language.as_str()),
+        );
+
+        headers.extend(self.headers.clone());
+
+        let client_builder = transport::client_builder(
+            self.user_agent,
+            self.user_agent_suffix,
+            headers,
+            self.connection_timeout_secs,
+            self.request_timeout_secs,
+            self.follow_redirects,
+        );
+
+        let client = Transport::new(client_builder.build().unwrap())
+            .with_max_concurrency(std::num::NonZeroUsize::new(self.max_concurrency))
+            .with_rate_limit_retries(self.rate_limit_retries, self.max_retry_after);
+
+        #[cfg(feature = "cache")]
+        let client = client.with_cache(self.cache_capacity, self.cache_ttl);
+
+        LookupClient {
+            client,
+            base_url: self.base_url.to_string(),
+            version: self.locatieserver_version.to_string(),
+            max_results: self.max_results,
+            include_historical: self.include_historical,
+        }
+    }
+}
 
-        LookupClient { client }
+impl<'a> Default for LookupClientBuilder<'a> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 impl<'a> LookupClientBuilder<'a> {
-    pub fn new(user_agent: &'a str) -> Self {
+    pub fn new() -> Self {
         Self {
-            user_agent,
+            user_agent: crate::DEFAULT_USER_AGENT,
+            user_agent_suffix: true,
+            base_url: LookupClient::GEODATA_NATIONAALGEOREGISTER_NL,
+            headers: HeaderMap::new(),
+            #[cfg(feature = "cache")]
+            cache_capacity: 0,
+            #[cfg(feature = "cache")]
+            cache_ttl: None,
+            max_results: usize::MAX,
+            include_historical: false,
+            language: Language::Dutch,
+            locatieserver_version: LookupClient::LOCATIESERVER_VERSION,
             connection_timeout_secs: 10,
             request_timeout_secs: 30,
+            follow_redirects: true,
+            max_concurrency: 0,
+            rate_limit_retries: 0,
+            max_retry_after: Duration::from_secs(60),
+        }
+    }
+
+    /// Fail suggestions with [`Error::TooManyResults`] when more than `max_results`
+    /// locations match, instead of returning the first rows. Unlimited by default.
+    pub fn max_results(&mut self, max_results: usize) -> &mut Self {
+        self.max_results = max_results;
+        self
+    }
+
+    /// Also find withdrawn addresses (`Naamgeving ingetrokken`) on a lot, e.g. for a title
+    /// search. Only current addresses are found by default; see [`SuggestDoc::status`]
+    /// to tell them apart.
+    pub fn include_historical(&mut self, include_historical: bool) -> &mut Self {
+        self.include_historical = include_historical;
+        self
+    }
+
+    /// The preferred language of names, sent as the `Accept-Language` header (Dutch by default).
+    ///
+    /// This concerns the names in results: `weergavenaam`, `straatnaam`, `woonplaatsnaam`
+    /// and `gemeentenaam`. The locatieserver currently serves the official names regardless,
+    /// which are Frisian for some places (e.g. `Ljouwert`), so results may not differ yet.
+    pub fn language(&mut self, language: Language) -> &mut Self {
+        self.language = language;
+        self
+    }
+
+    /// The version of the locatieserver API to use, as in its urls (`v3_1` by default).
+    ///
+    /// The documents are always decoded as `v3_1` documents, so only `v3_1` is known to work.
+    pub fn locatieserver_version(&mut self, locatieserver_version: &'a str) -> &mut Self {
+        self.locatieserver_version = locatieserver_version;
+        self
+    }
+}
+
+/// The languages names can be requested in, see [`LookupClientBuilder::language`].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Language {
+    Dutch,
+    Frisian,
+}
+
+impl Language {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Language::Dutch => "nl",
+            Language::Frisian => "fy",
         }
     }
 }
 
 impl LookupClient {
     const GEODATA_NATIONAALGEOREGISTER_NL: &'static str = "https://api.pdok.nl/bzk";
+    const LOCATIESERVER_VERSION: &'static str = "v3_1";
+    const ADDRESSES_PAGE_SIZE: usize = 100;
+
+    /// The url of an endpoint of the configured locatieserver version, e.g. `suggest`
+    fn endpoint(&self, name: &str) -> String {
+        format!(
+            "{}/locatieserver/search/{}/{}",
+            self.base_url, self.version, name
+        )
+    }
 
     /// Perform a Geocoding lookup based on postal code and housenumber.
     /// Yields a list of possible matches.
@@ -67,52 +252,368 @@ impl LookupClient {
         postcode: &str,
         huisnummer: &str,
     ) -> Result<Vec<SuggestDoc>, Error> {
+        let (docs, _) = self
+            .suggest_concrete_with_meta(postcode, huisnummer)
+            .await?;
+
+        Ok(docs)
+    }
+
+    /// Perform a Geocoding lookup like [`LookupClient::suggest_concrete`], along with the
+    /// status and headers of the response, e.g. to honor rate limits.
+    pub async fn suggest_concrete_with_meta(
+        &self,
+        postcode: &str,
+        huisnummer: &str,
+    ) -> Result<(Vec<SuggestDoc>, ResponseMeta), Error> {
         let params = SuggestParams {
             q: format!("postcode:{} {}", postcode, huisnummer),
         };
 
-        let url = format!(
-            "{}/locatieserver/search/v3_1/suggest",
-            LookupClient::GEODATA_NATIONAALGEOREGISTER_NL
-        );
+        let url = self.endpoint("suggest");
 
         let client_response = self
             .client
-            .get(&url)
-            .query(&params)
-            .send()
-            .await
-            .map_err(NetworkProblem)?;
+            .send(self.client.get(&url).query(&params))
+            .await?;
+        let meta = ResponseMeta::from_response(&client_response);
+
+        let response: SuggestResponse = transport::decode_json(client_response).await?;
+        Ok((self.capped_docs(response.response)?, meta))
+    }
+
+    /// Perform a Geocoding lookup like [`LookupClient::suggest_concrete`], retrying with a
+    /// relaxed query when nothing matches, e.g. for addresses from voice input.
+    ///
+    /// The relaxed query only uses the digits of the postal code, so a postal code with
+    /// wrong letters still yields suggestions in the same area. A postal code without
+    /// digits is not retried.
+    pub async fn suggest_concrete_fuzzy(
+        &self,
+        postcode: &str,
+        huisnummer: &str,
+    ) -> Result<FuzzySuggestions, Error> {
+        let docs = self.suggest_concrete(postcode, huisnummer).await?;
+
+        if !docs.is_empty() {
+            return Ok(FuzzySuggestions { docs, fuzzy: false });
+        }
+
+        let digits: String = postcode.chars().filter(char::is_ascii_digit).collect();
+        if digits.is_empty() {
+            return Ok(FuzzySuggestions { docs, fuzzy: false });
+        }
+
+        let params = SuggestParams {
+            q: format!("{} {}", digits, huisnummer),
+        };
+
+        let url = self.endpoint("suggest");
+
+        let response: SuggestResponse = self
+            .client
+            .send_json(self.client.get(&url).query(&params))
+            .await?;
+
+        Ok(FuzzySuggestions {
+            docs: self.capped_docs(response.response)?,
+            fuzzy: true,
+        })
+    }
+
+    /// Suggest the streets in a woonplaats whose name starts with `street_prefix`, e.g. for
+    /// address forms that cascade from woonplaats to street to housenumber.
+    ///
+    /// An empty prefix suggests any street in the woonplaats.
+    pub async fn suggest_streets(
+        &self,
+        woonplaats: &str,
+        street_prefix: &str,
+    ) -> Result<Vec<SuggestDoc>, Error> {
+        let woonplaats = format!("woonplaatsnaam:{}", escape_solr(woonplaats.trim()));
+        let q = match street_prefix.trim() {
+            "" => woonplaats,
+            prefix => format!("straatnaam:{}* and {}", escape_solr(prefix), woonplaats),
+        };
 
-        let response: SuggestResponse = client_response.json().await.map_err(JsonProblem)?;
-        Ok(response.response.docs)
+        let url = self.endpoint("suggest");
+        let u =
+            url::Url::parse_with_params(&url, &[("q", q.as_str()), ("fq", "type:weg")]).unwrap();
+
+        let response: SuggestResponse = self.client.get_json(u.as_str()).await?;
+
+        self.capped_docs(response.response)
+    }
+
+    /// Suggest locations for free text, e.g. typed into a search box, along with a
+    /// correction of the text when it looks misspelled.
+    ///
+    /// Unlike the other suggest methods this does not fail on many matches; the best ones
+    /// are returned.
+    pub async fn suggest(&self, q: &str) -> Result<SuggestResult, Error> {
+        let url = self.endpoint("suggest");
+        let u = url::Url::parse_with_params(&url, &[("q", q)]).unwrap();
+
+        let response: SuggestResponse = self.client.get_json(u.as_str()).await?;
+
+        Ok(SuggestResult {
+            did_you_mean: response.did_you_mean(),
+            docs: response.response.docs,
+        })
+    }
+
+    /// Lookup the address with exactly the given postal code and housenumber.
+    ///
+    /// Unlike [`LookupClient::suggest_concrete`], nearby housenumbers are not accepted:
+    /// yields `None` when the best suggestion is not an exact match.
+    pub async fn lookup_exact(
+        &self,
+        postcode: &str,
+        huisnummer: &str,
+    ) -> Result<Option<LookupDoc>, Error> {
+        let normalize = |value: &str| value.replace(' ', "").to_uppercase();
+        let (postcode, huisnummer) = (normalize(postcode), normalize(huisnummer));
+
+        let suggestions = self.suggest_concrete(&postcode, &huisnummer).await?;
+        let best = match suggestions.first() {
+            Some(best) => best,
+            None => return Ok(None),
+        };
+
+        let docs = self.lookup_suggestion(best).await?;
+
+        Ok(docs.into_iter().find(|doc| {
+            normalize(&doc.postcode) == postcode && normalize(&doc.huis_nlt) == huisnummer
+        }))
+    }
+
+    /// Whether the postal code and housenumber form an existing address, e.g. to validate a
+    /// form, with a single request.
+    ///
+    /// Solr scores are not comparable between queries, so rather than on the score this
+    /// relies on the best suggestion matching the postal code and housenumber. A housenumber
+    /// without letter or addition, like `26`, also matches `26A`.
+    pub async fn address_exists(&self, postcode: &str, huisnummer: &str) -> Result<bool, Error> {
+        let normalize = |value: &str| value.replace(' ', "").to_uppercase();
+        let (postcode, huisnummer) = (normalize(postcode), normalize(huisnummer));
+
+        let url = self.endpoint("suggest");
+        let q = format!("postcode:{} {}", postcode, huisnummer);
+        let u = url::Url::parse_with_params(
+            &url,
+            &[("q", q.as_str()), ("fq", "type:adres"), ("rows", "1")],
+        )
+        .unwrap();
+
+        let response: SuggestResponse = self.client.get_json(u.as_str()).await?;
+
+        let best = match response.response.docs.first() {
+            Some(best) => best,
+            None => return Ok(false),
+        };
+
+        Ok(best.parse_weergavenaam().is_some_and(|address| {
+            let rest = address.huisnummer.strip_prefix(huisnummer.as_str());
+            address.postcode == postcode
+                && rest.is_some_and(|rest| !rest.starts_with(|c: char| c.is_ascii_digit()))
+        }))
     }
 
     /// Lookup a specific location id.
     ///
     /// Returns a 1:1 representation of the SolrReponse.
     pub async fn lookup(&self, id: &str) -> Result<Vec<LookupDoc>, Error> {
-        let url = format!(
-            "{}/locatieserver/search/v3_1/lookup",
-            LookupClient::GEODATA_NATIONAALGEOREGISTER_NL
-        );
+        self.lookup_docs(id).await
+    }
+
+    /// Lookup a specific location id, along with the status and headers of the response,
+    /// e.g. to honor rate limits.
+    pub async fn lookup_with_meta(
+        &self,
+        id: &str,
+    ) -> Result<(Vec<LookupDoc>, ResponseMeta), Error> {
+        self.lookup_docs_with_meta(id).await
+    }
+
+    /// Lookup the location of a suggestion.
+    pub async fn lookup_suggestion(
+        &self,
+        suggestion: &SuggestDoc,
+    ) -> Result<Vec<LookupDoc>, Error> {
+        self.lookup(&suggestion.id).await
+    }
+
+    /// Lookup the best suggestion for a postal code and housenumber.
+    ///
+    /// Like [`LookupClient::suggest_concrete`], the best suggestion may be a nearby
+    /// housenumber; use [`LookupClient::lookup_exact`] to only accept exact matches.
+    pub async fn suggest_and_lookup(
+        &self,
+        postcode: &str,
+        huisnummer: &str,
+    ) -> Result<Option<LookupDoc>, Error> {
+        let suggestions = self.suggest_concrete(postcode, huisnummer).await?;
+        let best = match suggestions.first() {
+            Some(best) => best,
+            None => return Ok(None),
+        };
+
+        Ok(self.lookup_suggestion(best).await?.into_iter().next())
+    }
+
+    /// Lookup a specific location id, decoding the documents as `T`
+    async fn lookup_docs<T: DeserializeOwned>(&self, id: &str) -> Result<Vec<T>, Error> {
+        let (docs, _) = self.lookup_docs_with_meta(id).await?;
+
+        Ok(docs)
+    }
+
+    async fn lookup_docs_with_meta<T: DeserializeOwned>(
+        &self,
+        id: &str,
+    ) -> Result<(Vec<T>, ResponseMeta), Error> {
+        let url = self.endpoint("lookup");
 
         let u = url::Url::parse_with_params(&url, &[("id", id)]).unwrap();
 
-        let client_response = self
-            .client
-            .get(u.as_str())
-            .send()
-            .await
-            .map_err(NetworkProblem)?;
+        let client_response = self.client.send(self.client.get(u.as_str())).await?;
+        let meta = ResponseMeta::from_response(&client_response);
+
+        let response: LookupResponse<T> = transport::decode_json(client_response).await?;
+
+        Ok((response.response.docs, meta))
+    }
+
+    /// Lookup the geometry of a location id, e.g. the boundary of a woonplaats (`wpl-`)
+    /// or wijk (`wbd-`), in WGS84.
+    ///
+    /// When only a rijksdriehoek geometry is available, it is converted to WGS84.
+    /// Yields `None` for ids without a geometry.
+    pub async fn lookup_geometry(&self, id: &str) -> Result<Option<geojson::Geometry>, Error> {
+        use geo::algorithm::map_coords::MapCoords;
+
+        let doc = match self
+            .lookup_docs::<GeometryDoc>(id)
+            .await?
+            .into_iter()
+            .next()
+        {
+            Some(doc) => doc,
+            None => return Ok(None),
+        };
+
+        let geometry = match (doc.geometrie_ll, doc.geometrie_rd) {
+            (Some(wkt), _) => wkt_to_geo(&wkt).map_err(GeometryError)?,
+            (None, Some(wkt)) => wkt_to_geo(&wkt)
+                .map_err(GeometryError)?
+                .map_coords(|c| crate::util::coordinate_rijksdriehoek_to_wgs84(c.x, c.y)),
+            (None, None) => return Ok(None),
+        };
+
+        Ok(Some(geojson::Geometry::new(geojson::Value::from(
+            &geometry,
+        ))))
+    }
+
+    /// Lookup the centroid of a location id, in both WGS84 and rijksdriehoek.
+    ///
+    /// When the locatieserver only provides one of both, the other is reprojected.
+    pub async fn lookup_coordinates(&self, id: &str) -> Result<AddressCoordinates, Error> {
+        let doc = self
+            .lookup_docs::<GeometryDoc>(id)
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(EmptyResponse)?;
+
+        let parse = |wkt: Option<String>| {
+            wkt.map(|wkt| wkt_to_point(&wkt).map_err(GeometryError))
+                .transpose()
+        };
 
-        let response: LookupResponse = client_response.json().await.map_err(JsonProblem)?;
+        let (wgs84, rijksdriehoek) = match (parse(doc.centroide_ll)?, parse(doc.centroide_rd)?) {
+            (Some(wgs84), Some(rijksdriehoek)) => (wgs84, rijksdriehoek),
+            (Some(wgs84), None) => {
+                let rd = crate::util::coordinate_wgs84_to_rijksdriehoek(wgs84.x(), wgs84.y());
+                (wgs84, rd.into())
+            }
+            (None, Some(rijksdriehoek)) => {
+                let ll = crate::util::coordinate_rijksdriehoek_to_wgs84(
+                    rijksdriehoek.x(),
+                    rijksdriehoek.y(),
+                );
+                (ll.into(), rijksdriehoek)
+            }
+            (None, None) => return Err(EmptyResponse),
+        };
+
+        Ok(AddressCoordinates {
+            wgs84,
+            rijksdriehoek,
+        })
+    }
+
+    /// Lookup several location ids in a single request.
+    ///
+    /// The lookup endpoint only accepts a single id, so this queries the free endpoint for
+    /// all fields of the given ids instead. The results follow the order of `ids`,
+    /// ids that are not found are left out.
+    pub async fn lookup_many(&self, ids: &[&str]) -> Result<Vec<LookupDoc>, Error> {
+        if ids.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let url = self.endpoint("free");
+
+        let q = ids
+            .iter()
+            .map(|id| format!("id:{}", escape_solr(id)))
+            .collect::<Vec<_>>()
+            .join(" OR ");
+        let rows = ids.len().to_string();
+
+        let u =
+            url::Url::parse_with_params(&url, &[("q", q.as_str()), ("fl", "*"), ("rows", &rows)])
+                .unwrap();
+
+        let response: LookupResponse<LookupDoc> = self.client.get_json(u.as_str()).await?;
+
+        let mut docs = response.response.docs;
+        docs.sort_by_key(|doc| ids.iter().position(|id| *id == doc.id));
+
+        Ok(docs)
+    }
+
+    /// Lookup the address of a nummeraanduiding, e.g. from a BAG record.
+    /// Yields `None` when the locatieserver does not know the nummeraanduiding.
+    pub async fn lookup_nummeraanduiding(&self, naid: &str) -> Result<Option<LookupDoc>, Error> {
+        let url = self.endpoint("free");
+        let q = format!("nummeraanduiding_id:{}", escape_solr(naid.trim()));
 
-        Ok(response.response.docs)
+        let u = url::Url::parse_with_params(
+            &url,
+            &[
+                ("q", q.as_str()),
+                ("fq", "type:adres"),
+                ("fl", "*"),
+                ("rows", "1"),
+            ],
+        )
+        .unwrap();
+
+        let response: LookupResponse<LookupDoc> = self.client.get_json(u.as_str()).await?;
+
+        Ok(response.response.docs.into_iter().next())
     }
 
     /// Get suggestions on addresses related to a lot
     /// Yields a list of possible matches.
+    ///
+    /// Only the first 10 addresses are returned, use [`LookupClient::addresses_for_lot_stream`]
+    /// for lots that may have more.
+    ///
+    /// Only current addresses are returned, unless the client was built with
+    /// [`LookupClientBuilder::include_historical`].
     pub async fn suggest_addresses_for_lot(
         &self,
         lot_code: &str,
@@ -124,45 +625,286 @@ impl LookupClient {
             lot_code, lot_letter, lot_number
         );
 
-        let url = format!(
-            "{}/locatieserver/search/v3_1/free",
-            LookupClient::GEODATA_NATIONAALGEOREGISTER_NL
-        );
         // Example: https://api.pdok.nl/bzk/locatieserver/search/v3_1/free?q=gekoppeld_perceel:HTT02-M-5038
-        let u =
-            url::Url::parse_with_params(&url, &[("q", query), ("fq", "type:adres".to_string())])
-                .unwrap();
+        self.free(&query, self.lot_address_filters(), 10).await
+    }
 
-        let client_response = self
-            .client
-            .get(u.as_str())
-            .send()
-            .await
-            .map_err(NetworkProblem)?;
+    /// Page through all addresses related to a lot, e.g. the many addresses coupled to a
+    /// large agricultural lot.
+    ///
+    /// The next page is only requested once the addresses of the current page have been
+    /// consumed. Unlike the other queries, the stream is not limited by `max_results`.
+    /// Like [`LookupClient::suggest_addresses_for_lot`], withdrawn addresses are only
+    /// included with [`LookupClientBuilder::include_historical`].
+    pub fn addresses_for_lot_stream(
+        &self,
+        lot_code: &str,
+        lot_letter: &str,
+        lot_number: &str,
+    ) -> impl Stream<Item = Result<SuggestDoc, Error>> + '_ {
+        struct State {
+            query: String,
+            start: usize,
+            page: std::vec::IntoIter<SuggestDoc>,
+            exhausted: bool,
+        }
 
-        let response: SuggestResponse = client_response.json().await.map_err(JsonProblem)?;
+        let state = State {
+            query: format!(
+                "gekoppeld_perceel:{}-{}-{}",
+                lot_code, lot_letter, lot_number
+            ),
+            start: 0,
+            page: Vec::new().into_iter(),
+            exhausted: false,
+        };
+
+        futures::stream::unfold(state, move |mut state| async move {
+            loop {
+                if let Some(doc) = state.page.next() {
+                    return Some((Ok(doc), state));
+                }
+
+                if state.exhausted {
+                    return None;
+                }
+
+                let page = self
+                    .free_page(
+                        &state.query,
+                        self.lot_address_filters(),
+                        Self::ADDRESSES_PAGE_SIZE,
+                        state.start,
+                    )
+                    .await;
+
+                match page {
+                    Ok(response) => {
+                        state.start += response.docs.len();
+                        state.exhausted =
+                            response.docs.is_empty() || state.start >= response.num_found;
+                        state.page = response.docs.into_iter();
+                    }
+                    Err(e) => {
+                        state.exhausted = true;
+                        return Some((Err(e), state));
+                    }
+                }
+            }
+        })
+    }
+
+    /// The filter queries for the addresses on a lot, leaving out withdrawn addresses
+    /// unless historical addresses are included
+    fn lot_address_filters(&self) -> &'static [&'static str] {
+        if self.include_historical {
+            &["type:adres"]
+        } else {
+            &["type:adres", "-status:\"Naamgeving ingetrokken\""]
+        }
+    }
+
+    /// Perform a free Solr query, e.g. `q=straatnaam:Castellastraat` with `fq=type:adres`.
+    /// Each filter query in `fq` is sent as a separate parameter.
+    /// Yields at most `rows` matches.
+    pub async fn free(&self, q: &str, fq: &[&str], rows: usize) -> Result<Vec<SuggestDoc>, Error> {
+        let response = self.free_page(q, fq, rows, 0).await?;
+
+        self.capped_docs(response)
+    }
+
+    /// Perform a free Solr query, skipping the first `start` matches
+    async fn free_page(
+        &self,
+        q: &str,
+        fq: &[&str],
+        rows: usize,
+        start: usize,
+    ) -> Result<SolrResponse<SuggestDoc>, Error> {
+        let url = self.endpoint("free");
+
+        let mut u = url::Url::parse_with_params(&url, &[("q", q)]).unwrap();
+        {
+            let mut pairs = u.query_pairs_mut();
+            for filter in fq {
+                pairs.append_pair("fq", filter);
+            }
+            pairs.append_pair("rows", &rows.to_string());
+            if start > 0 {
+                pairs.append_pair("start", &start.to_string());
+            }
+        }
+
+        let response: SuggestResponse = self.client.get_json(u.as_str()).await?;
+
+        Ok(response.response)
+    }
 
-        Ok(response.response.docs)
+    /// The matched documents, unless more than `max_results` locations matched
+    fn capped_docs<T>(&self, response: SolrResponse<T>) -> Result<Vec<T>, Error> {
+        if response.num_found > self.max_results {
+            return Err(TooManyResults {
+                num_found: response.num_found,
+                cap: self.max_results,
+            });
+        }
+
+        Ok(response.docs)
     }
 
     /// Check if the API is up by looking up our office
     pub async fn lookup_tg_office(&self) -> Result<Vec<LookupDoc>, Error> {
         self.lookup("adr-5826c02550308f6da19e4feb5eb97ec8").await
     }
+
+    /// Check if the API is up by looking up our office, failing when it takes longer than
+    /// `timeout` regardless of the configured request timeout.
+    ///
+    /// The cache is bypassed. Yields `false` when the API responds with an error status,
+    /// and fails on network problems and timeouts.
+    pub async fn probe(&self, timeout: Duration) -> Result<bool, Error> {
+        let url = self.endpoint("lookup");
+
+        let request = self
+            .client
+            .get(&url)
+            .query(&[("id", "adr-5826c02550308f6da19e4feb5eb97ec8")]);
+        let request = transport::with_timeout(request, timeout);
+
+        let response = match self.client.probe(request).await? {
+            Some(response) => response,
+            None => return Ok(false),
+        };
+        let response: LookupResponse<LookupDoc> = transport::decode_json(response).await?;
+
+        Ok(!response.response.docs.is_empty())
+    }
 }
 
 /// A specific location that was looked up.
 /// Contains references to the lot, building and address.
+///
+/// Only `id` is present for every type of location. The other fields are empty when the
+/// locatieserver omits them, e.g. for a street (`weg`) or a woonplaats.
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct LookupDoc {
     pub id: String,
+    /// Address only
+    #[serde(default)]
     pub gekoppeld_perceel: Vec<String>,
+    /// Address only
+    #[serde(default)]
     pub nummeraanduiding_id: String,
+    /// Address only
+    #[serde(default)]
     pub adresseerbaarobject_id: String,
+    /// Address only, and missing for some streets
+    #[serde(default)]
     pub postcode: String,
+    /// Address only
+    #[serde(default)]
     pub huis_nlt: String,
+    /// Missing for a woonplaats or gemeente
+    #[serde(default)]
     pub straatnaam: String,
+    /// Missing for a gemeente
+    #[serde(default)]
     pub woonplaatsnaam: String,
+    /// Address only, e.g. `Naamgeving uitgegeven` or `Naamgeving ingetrokken`
+    #[serde(default)]
+    pub status: String,
+}
+
+impl LookupDoc {
+    /// The lots the address is on, skipping any `gekoppeld_perceel` that cannot be parsed.
+    pub fn percelen(&self) -> Vec<Perceelsleutel> {
+        self.gekoppeld_perceel
+            .iter()
+            .filter_map(|code| Perceelsleutel::parse(code))
+            .collect()
+    }
+}
+
+/// The key of a lot (kadastrale aanduiding), as in the `gekoppeld_perceel` of an address.
+///
+/// Displays in the locatieserver format, e.g. `HTT02-M-5038`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Perceelsleutel {
+    /// The kadastrale gemeentecode, e.g. `HTT02`
+    pub gemeentecode: String,
+    pub sectie: String,
+    pub perceelnummer: u64,
+    /// The index letter, `A` for an apartment right or `G` for a whole lot
+    pub index_letter: Option<char>,
+    /// The index number of an apartment right
+    pub index_nummer: Option<u32>,
+}
+
+impl Perceelsleutel {
+    /// Parse a key like `HTT02-M-5038` or `HTT02 M 5038`, optionally followed by an
+    /// index letter and number, e.g. `ASD04-F-1234-A-1` or `ASD04 F 1234 A1`.
+    pub fn parse(code: &str) -> Option<Self> {
+        let mut parts = code
+            .split(|c: char| c == '-' || c.is_whitespace())
+            .filter(|part| !part.is_empty());
+
+        let gemeentecode = parts.next()?;
+        let sectie = parts.next()?;
+        let perceelnummer = parts.next()?;
+
+        let valid_gemeentecode =
+            gemeentecode.len() == 5 && gemeentecode.chars().all(|c| c.is_ascii_alphanumeric());
+        let valid_sectie =
+            (1..=2).contains(&sectie.len()) && sectie.chars().all(|c| c.is_ascii_alphabetic());
+
+        if !valid_gemeentecode || !valid_sectie {
+            return None;
+        }
+
+        // The index may follow as separate parts, or be attached to each other
+        let index: String = parts.collect();
+        let (index_letter, index_nummer) = match index.chars().next() {
+            None => (None, None),
+            Some(letter @ ('A' | 'G')) => {
+                let nummer = &index[1..];
+                let nummer = if nummer.is_empty() {
+                    None
+                } else {
+                    Some(nummer.parse().ok()?)
+                };
+                (Some(letter), nummer)
+            }
+            Some(_) => return None,
+        };
+
+        Some(Perceelsleutel {
+            gemeentecode: gemeentecode.to_string(),
+            sectie: sectie.to_string(),
+            perceelnummer: perceelnummer.parse().ok()?,
+            index_letter,
+            index_nummer,
+        })
+    }
+}
+
+impl std::fmt::Display for Perceelsleutel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-{}-{}",
+            self.gemeentecode, self.sectie, self.perceelnummer
+        )?;
+
+        if let Some(letter) = self.index_letter {
+            write!(f, "-{}", letter)?;
+        }
+
+        if let Some(nummer) = self.index_nummer {
+            write!(f, "-{}", nummer)?;
+        }
+
+        Ok(())
+    }
 }
 
 impl PartialEq for LookupDoc {
@@ -173,6 +915,12 @@ impl PartialEq for LookupDoc {
 
 impl Eq for LookupDoc {}
 
+impl std::hash::Hash for LookupDoc {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
 impl PartialOrd for LookupDoc {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
@@ -191,37 +939,176 @@ impl Ord for LookupDoc {
     }
 }
 
-// See: https://api.pdok.nl/bzk/locatieserver/search/v3_1/ui/#/Locatieserver/suggest
-#[derive(Serialize)]
-struct SuggestParams {
-    q: String,
-}
+/// Escape the characters that have a meaning in a Solr query, including whitespace
+fn escape_solr(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        if c.is_whitespace() || "+-&|!(){}[]^\"~*?:\\/".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
+// See: https://api.pdok.nl/bzk/locatieserver/search/v3_1/ui/#/Locatieserver/suggest
+#[derive(Serialize)]
+struct SuggestParams {
+    q: String,
+}
 
 /// One element of the set of suggestions as done by the geocoding service.
 ///
 /// Probably only the best result is relevant for our search.
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct SuggestDoc {
     pub id: String,
     #[serde(rename = "type")]
     pub result_type: String,
     pub weergavenaam: String,
     pub score: f64,
+    /// Address only, e.g. `Naamgeving uitgegeven` or `Naamgeving ingetrokken`
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub status: String,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
-struct SolrResponse<T> {
-    docs: Vec<T>,
+impl SuggestDoc {
+    /// Split the weergavenaam of an address, like `"Castellastraat 26, 6512EX Nijmegen"`,
+    /// into its components.
+    ///
+    /// Yields `None` for other types of results, whose weergavenaam has a different format.
+    pub fn parse_weergavenaam(&self) -> Option<ParsedAddress> {
+        if self.result_type != "adres" {
+            return None;
+        }
+
+        let (street, place) = self.weergavenaam.split_once(", ")?;
+        let (straat, huisnummer) = street.rsplit_once(' ')?;
+        let (postcode, woonplaats) = place.split_once(' ')?;
+
+        let is_postcode = postcode.len() == 6
+            && postcode.is_ascii()
+            && postcode[..4].chars().all(|c| c.is_ascii_digit())
+            && postcode[4..].chars().all(|c| c.is_ascii_uppercase());
+
+        if !is_postcode {
+            return None;
+        }
+
+        Some(ParsedAddress {
+            straat: straat.to_string(),
+            huisnummer: huisnummer.to_string(),
+            postcode: postcode.to_string(),
+            woonplaats: woonplaats.to_string(),
+        })
+    }
 }
 
+/// Suggestions from [`LookupClient::suggest_concrete_fuzzy`].
 #[derive(Serialize, Deserialize, Debug)]
-struct SuggestResponse {
-    response: SolrResponse<SuggestDoc>,
+pub struct FuzzySuggestions {
+    pub docs: Vec<SuggestDoc>,
+    /// Whether the suggestions came from the relaxed fallback query
+    pub fuzzy: bool,
+}
+
+/// The components of the weergavenaam of an address.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ParsedAddress {
+    pub straat: String,
+    /// The housenumber, including any letter or addition, e.g. `26A-1`
+    pub huisnummer: String,
+    pub postcode: String,
+    pub woonplaats: String,
+}
+
+/// The `response` member of a locatieserver response, e.g. to pass responses on as is
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SolrResponse<T> {
+    /// The total number of matches, of which only the first rows are in `docs`
+    #[serde(rename = "numFound", default)]
+    pub num_found: usize,
+    /// The offset of the first of the `docs` in all matches
+    #[serde(default)]
+    pub start: usize,
+    #[serde(rename = "maxScore", default, skip_serializing_if = "Option::is_none")]
+    pub max_score: Option<f64>,
+    pub docs: Vec<T>,
+}
+
+/// A response of the suggest and free endpoints
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SuggestResponse {
+    pub response: SolrResponse<SuggestDoc>,
+    /// Present when the query contains words the index does not know
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub spellcheck: Option<Spellcheck>,
+}
+
+impl SuggestResponse {
+    /// The corrected query of the first collation, if any.
+    pub fn did_you_mean(&self) -> Option<String> {
+        self.spellcheck.as_ref()?.collation()
+    }
+}
+
+/// The `spellcheck` member of a suggest response.
+///
+/// Solr serializes both members as flat lists of alternating names and values, e.g.
+/// `["collation", "castellastraat nijmegen"]`, so they are kept as is.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Spellcheck {
+    /// The misspelled words along with their suggested corrections
+    #[serde(default)]
+    pub suggestions: Vec<serde_json::Value>,
+    #[serde(default)]
+    pub collations: Vec<serde_json::Value>,
+}
+
+impl Spellcheck {
+    /// The first collation, which is either the corrected query itself or, with extended
+    /// results, an object with the query in `collationQuery`.
+    pub fn collation(&self) -> Option<String> {
+        self.collations
+            .chunks_exact(2)
+            .filter(|pair| pair[0] == "collation")
+            .find_map(|pair| match &pair[1] {
+                serde_json::Value::String(query) => Some(query.clone()),
+                value => Some(value.get("collationQuery")?.as_str()?.to_string()),
+            })
+    }
+}
+
+/// Suggestions from [`LookupClient::suggest`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SuggestResult {
+    pub docs: Vec<SuggestDoc>,
+    /// A correction of the query, to offer as "did you mean …?"
+    pub did_you_mean: Option<String>,
+}
+
+/// A response of the lookup endpoint, with documents of type `T`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct LookupResponse<T> {
+    pub response: SolrResponse<T>,
 }
 
+/// The geometry fields of a location of any type, as WKT
 #[derive(Deserialize, Debug)]
-struct LookupResponse {
-    response: SolrResponse<LookupDoc>,
+struct GeometryDoc {
+    geometrie_ll: Option<String>,
+    geometrie_rd: Option<String>,
+    centroide_ll: Option<String>,
+    centroide_rd: Option<String>,
+}
+
+/// The centroid of a location in both coordinate spaces, with longitude in x for WGS84
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct AddressCoordinates {
+    pub wgs84: Point<f64>,
+    pub rijksdriehoek: Point<f64>,
 }
 
 #[cfg(test)]
@@ -235,27 +1122,29 @@ mod test {
         };
     }
 
+    /// A locatieserver response without documents
+    fn empty_response() -> wiremock::ResponseTemplate {
+        wiremock::ResponseTemplate::new(200)
+            .set_body_json(serde_json::json!({ "response": { "docs": [] } }))
+    }
+
     #[test]
     fn concrete_address() {
         let postalcode = "6542WZ";
         let housenumber = "222";
-        let client = LookupClientBuilder::new("pdok-apis lookup").build();
-
-        let suggest_doc = aw!(client.suggest_concrete(postalcode, housenumber));
-
-        let id = suggest_doc.unwrap().first().unwrap().id.clone();
+        let client = LookupClientBuilder::new().build();
 
-        assert_eq!(id, "adr-2fe93c94378bb179c424cf9918662375");
-
-        let lookup_doc = aw!(client.lookup(&id));
-        let street_name = lookup_doc.unwrap().first().unwrap().straatnaam.clone();
+        let lookup_doc = aw!(client.suggest_and_lookup(postalcode, housenumber))
+            .unwrap()
+            .unwrap();
 
-        assert_eq!(street_name, "Oude Nonnendaalseweg");
+        assert_eq!(lookup_doc.id, "adr-2fe93c94378bb179c424cf9918662375");
+        assert_eq!(lookup_doc.straatnaam, "Oude Nonnendaalseweg");
     }
 
     #[test]
     fn suggest_address_for_lot() {
-        let client = LookupClientBuilder::new("pdok-apis lookup").build();
+        let client = LookupClientBuilder::new().build();
 
         // TG office plot
         let result = aw!(client.suggest_addresses_for_lot("HTT02", "M", "5038"));
@@ -265,9 +1154,167 @@ mod test {
         assert_eq!(id, "adr-03b34aeb91028a913c05006049ed3245");
     }
 
+    #[test]
+    fn lookup_many_mock() {
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            // The free endpoint returns the documents in its own order
+            let docs = serde_json::json!({
+                "response": {
+                    "numFound": 3,
+                    "docs": [{ "id": "adr-3" }, { "id": "adr-1" }, { "id": "adr-\"2\\" }]
+                }
+            });
+            Mock::given(matchers::path("/locatieserver/search/v3_1/free"))
+                .and(matchers::query_param(
+                    "q",
+                    r#"id:adr\-1 OR id:adr\-\"2\\ OR id:adr\-3"#,
+                ))
+                .and(matchers::query_param("rows", "3"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(docs))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let client = LookupClientBuilder::new().base_url(&uri).build();
+
+            let docs = client
+                .lookup_many(&["adr-1", "adr-\"2\\", "adr-3"])
+                .await
+                .unwrap();
+            let ids: Vec<_> = docs.iter().map(|doc| doc.id.as_str()).collect();
+
+            assert_eq!(ids, ["adr-1", "adr-\"2\\", "adr-3"]);
+        });
+    }
+
+    #[test]
+    fn include_historical() {
+        use futures::StreamExt;
+        use wiremock::{matchers, Mock, MockServer, Request, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            // Two current addresses and one withdrawn address, which the status filter leaves out
+            Mock::given(matchers::query_param("q", "gekoppeld_perceel:HTT02-M-5038"))
+                .respond_with(|request: &Request| {
+                    let current_only = request
+                        .url
+                        .query_pairs()
+                        .any(|(key, value)| key == "fq" && value.starts_with("-status:"));
+                    let docs: Vec<_> = [
+                        ("adr-1", "Naamgeving uitgegeven"),
+                        ("adr-2", "Naamgeving uitgegeven"),
+                        ("adr-3", "Naamgeving ingetrokken"),
+                    ]
+                    .into_iter()
+                    .filter(|(_, status)| !current_only || *status == "Naamgeving uitgegeven")
+                    .map(|(id, status)| {
+                        serde_json::json!({
+                            "id": id, "type": "adres", "weergavenaam": id, "score": 1.0,
+                            "status": status
+                        })
+                    })
+                    .collect();
+                    let body = serde_json::json!({
+                        "response": { "numFound": docs.len(), "docs": docs }
+                    });
+
+                    ResponseTemplate::new(200).set_body_json(body)
+                })
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let current = LookupClientBuilder::new().base_url(&uri).build();
+            let historical = LookupClientBuilder::new()
+                .base_url(&uri)
+                .include_historical(true)
+                .build();
+
+            let suggestions = current.suggest_addresses_for_lot("HTT02", "M", "5038");
+            assert_eq!(suggestions.await.unwrap().len(), 2);
+
+            let suggestions = historical.suggest_addresses_for_lot("HTT02", "M", "5038");
+            let suggestions = suggestions.await.unwrap();
+            assert_eq!(suggestions.len(), 3);
+            assert_eq!(suggestions[2].status, "Naamgeving ingetrokken");
+
+            let streamed = current.addresses_for_lot_stream("HTT02", "M", "5038");
+            assert_eq!(streamed.count().await, 2);
+
+            let streamed = historical.addresses_for_lot_stream("HTT02", "M", "5038");
+            assert_eq!(streamed.count().await, 3);
+        });
+    }
+
+    #[test]
+    fn addresses_for_lot_stream() {
+        use futures::StreamExt;
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        let page = |ids: &[&str]| {
+            let docs: Vec<_> = ids
+                .iter()
+                .map(|id| {
+                    serde_json::json!({
+                        "id": id, "type": "adres", "weergavenaam": id, "score": 1.0
+                    })
+                })
+                .collect();
+            serde_json::json!({ "response": { "numFound": 3, "docs": docs } })
+        };
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(matchers::query_param("q", "gekoppeld_perceel:HTT02-M-5038"))
+                .and(matchers::query_param("start", "2"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(page(&["adr-3"])))
+                .expect(1)
+                .mount(&server)
+                .await;
+            Mock::given(matchers::query_param("q", "gekoppeld_perceel:HTT02-M-5038"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(page(&["adr-1", "adr-2"])))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let client = LookupClientBuilder::new().base_url(&uri).build();
+
+            let ids: Vec<_> = client
+                .addresses_for_lot_stream("HTT02", "M", "5038")
+                .map(|doc| doc.unwrap().id)
+                .collect()
+                .await;
+
+            assert_eq!(ids, ["adr-1", "adr-2", "adr-3"]);
+        });
+    }
+
+    #[test]
+    fn free_query() {
+        let client = LookupClientBuilder::new().build();
+
+        let result = aw!(client.free(
+            "straatnaam:Castellastraat",
+            &["type:adres", "woonplaatsnaam:Nijmegen"],
+            5
+        ))
+        .unwrap();
+
+        assert!(!result.is_empty() && result.len() <= 5);
+        assert!(result
+            .iter()
+            .all(|doc| doc.result_type == "adres" && doc.weergavenaam.contains("Castellastraat")));
+    }
+
     #[test]
     fn lookup_id() {
-        let client = LookupClientBuilder::new("pdok-apis lookup").build();
+        let client = LookupClientBuilder::new().build();
 
         // TG office ID
         let result = aw!(client.lookup_tg_office()).unwrap();
@@ -280,4 +1327,937 @@ mod test {
         assert_eq!(number, "26");
         assert_eq!(postcode, "6512EX");
     }
+
+    #[test]
+    fn suggest_concrete_fuzzy() {
+        let client = LookupClientBuilder::new().build();
+
+        let exact = aw!(client.suggest_concrete_fuzzy("6512EX", "26")).unwrap();
+        assert!(!exact.fuzzy);
+
+        // The TG office is at 6512EX, not 6512XX
+        let suggestions = aw!(client.suggest_concrete_fuzzy("6512XX", "26")).unwrap();
+        assert!(!suggestions.docs.is_empty());
+        assert!(suggestions.docs[0].weergavenaam.contains("6512"));
+    }
+
+    #[test]
+    fn suggest_concrete_fuzzy_fallback() {
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            let office = serde_json::json!({
+                "response": { "numFound": 1, "docs": [{
+                    "id": "adr-1", "type": "adres", "score": 1.0,
+                    "weergavenaam": "Castellastraat 26, 6512EX Nijmegen"
+                }] }
+            });
+            Mock::given(matchers::query_param("q", "6512 26"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(office))
+                .expect(1)
+                .mount(&server)
+                .await;
+            // The strict queries find nothing
+            Mock::given(matchers::query_param("q", "postcode:6512XX 26"))
+                .respond_with(empty_response())
+                .expect(1)
+                .mount(&server)
+                .await;
+            Mock::given(matchers::query_param("q", "postcode:ABCDEF 26"))
+                .respond_with(empty_response())
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let client = LookupClientBuilder::new().base_url(&uri).build();
+
+            let suggestions = client.suggest_concrete_fuzzy("6512XX", "26").await.unwrap();
+            assert!(suggestions.fuzzy);
+            assert_eq!(suggestions.docs[0].id, "adr-1");
+
+            // Without digits there is no relaxed query to fall back to
+            let suggestions = client.suggest_concrete_fuzzy("ABCDEF", "26").await.unwrap();
+            assert!(!suggestions.fuzzy);
+            assert!(suggestions.docs.is_empty());
+        });
+    }
+
+    #[test]
+    fn suggest_streets() {
+        let client = LookupClientBuilder::new().build();
+
+        let streets = aw!(client.suggest_streets("Nijmegen", "Castell")).unwrap();
+
+        assert!(streets
+            .iter()
+            .any(|street| street.weergavenaam.starts_with("Castellastraat")));
+        assert!(streets.iter().all(|street| street.result_type == "weg"));
+    }
+
+    #[test]
+    fn suggest_streets_query() {
+        use wiremock::{matchers, Mock, MockServer};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(matchers::query_param(
+                "q",
+                "straatnaam:Sint\\ Annastr* and woonplaatsnaam:Nijmegen",
+            ))
+            .and(matchers::query_param("fq", "type:weg"))
+            .respond_with(empty_response())
+            .expect(1)
+            .mount(&server)
+            .await;
+            Mock::given(matchers::query_param("q", "woonplaatsnaam:Nijmegen"))
+                .respond_with(empty_response())
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let client = LookupClientBuilder::new().base_url(&uri).build();
+
+            client
+                .suggest_streets("Nijmegen", "Sint Annastr")
+                .await
+                .unwrap();
+            client.suggest_streets("Nijmegen", " ").await.unwrap();
+        });
+    }
+
+    #[test]
+    fn address_exists() {
+        let client = LookupClientBuilder::new().build();
+
+        assert!(aw!(client.address_exists("6512EX", "26")).unwrap());
+        assert!(aw!(client.address_exists("6512 ex", "26")).unwrap());
+        assert!(!aw!(client.address_exists("6512EX", "2600")).unwrap());
+    }
+
+    #[test]
+    fn address_exists_match() {
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(matchers::query_param("fq", "type:adres"))
+                .and(matchers::query_param("rows", "1"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "response": { "docs": [{
+                        "id": "adr-5826c02550308f6da19e4feb5eb97ec8",
+                        "type": "adres",
+                        "weergavenaam": "Castellastraat 26A, 6512EX Nijmegen",
+                        "score": 3.2
+                    }]}
+                })))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let client = LookupClientBuilder::new().base_url(&uri).build();
+
+            assert!(client.address_exists("6512EX", "26").await.unwrap());
+            assert!(client.address_exists("6512EX", "26a").await.unwrap());
+            assert!(!client.address_exists("6512EX", "2").await.unwrap());
+            assert!(!client.address_exists("6512EY", "26").await.unwrap());
+        });
+    }
+
+    #[test]
+    fn lookup_nummeraanduiding() {
+        let client = LookupClientBuilder::new().build();
+
+        let office = aw!(client.lookup_exact("6512EX", "26")).unwrap().unwrap();
+        let address = aw!(client.lookup_nummeraanduiding(&office.nummeraanduiding_id))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(address.postcode, "6512EX");
+        assert_eq!(address.huis_nlt, "26");
+        assert_eq!(address.straatnaam, "Castellastraat");
+    }
+
+    #[test]
+    fn lookup_nummeraanduiding_query() {
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(matchers::path("/locatieserver/search/v3_1/free"))
+                .and(matchers::query_param(
+                    "q",
+                    "nummeraanduiding_id:0268200000084126",
+                ))
+                .and(matchers::query_param("fq", "type:adres"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                    "response": { "docs": [{
+                        "id": "adr-1",
+                        "nummeraanduiding_id": "0268200000084126",
+                        "postcode": "6512EX",
+                        "huis_nlt": "26"
+                    }] }
+                })))
+                .mount(&server)
+                .await;
+            Mock::given(matchers::any())
+                .respond_with(empty_response())
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let client = LookupClientBuilder::new().base_url(&uri).build();
+
+            let address = client
+                .lookup_nummeraanduiding("0268200000084126")
+                .await
+                .unwrap();
+            assert_eq!(address.unwrap().postcode, "6512EX");
+
+            let missing = client.lookup_nummeraanduiding("0268200000000000").await;
+            assert!(missing.unwrap().is_none());
+        });
+    }
+
+    #[test]
+    fn lookup_exact() {
+        let client = LookupClientBuilder::new().build();
+
+        let doc = aw!(client.lookup_exact("6512 ex", "26")).unwrap().unwrap();
+        assert_eq!(doc.postcode, "6512EX");
+        assert_eq!(doc.huis_nlt, "26");
+
+        let doc = aw!(client.lookup_exact("6512EX", "9999")).unwrap();
+        assert!(doc.is_none());
+    }
+
+    #[test]
+    fn lookup_geometry() {
+        let client = LookupClientBuilder::new().build();
+
+        let woonplaats = aw!(client.free("Nijmegen", &["type:woonplaats"], 1)).unwrap();
+        let geometry = aw!(client.lookup_geometry(&woonplaats[0].id))
+            .unwrap()
+            .unwrap();
+
+        assert!(matches!(
+            geometry.value,
+            geojson::Value::Polygon(_) | geojson::Value::MultiPolygon(_)
+        ));
+    }
+
+    #[test]
+    fn too_many_results() {
+        let client = LookupClientBuilder::new().max_results(100).build();
+
+        let result = aw!(client.free("Kerkstraat", &["type:adres"], 10));
+        assert!(matches!(
+            result,
+            Err(TooManyResults { num_found, cap: 100 }) if num_found > 100
+        ));
+    }
+
+    #[test]
+    fn lookup_coordinates() {
+        let client = LookupClientBuilder::new().build();
+
+        let coordinates =
+            aw!(client.lookup_coordinates("adr-5826c02550308f6da19e4feb5eb97ec8")).unwrap();
+
+        assert!((coordinates.wgs84.x() - 5.85).abs() < 0.05);
+        assert!((coordinates.wgs84.y() - 51.83).abs() < 0.05);
+        assert!(crate::util::looks_like_rijksdriehoek(
+            coordinates.rijksdriehoek.0
+        ));
+    }
+
+    #[test]
+    fn lookup_many() {
+        let client = LookupClientBuilder::new().build();
+
+        let suggestions = aw!(client.free("Castellastraat Nijmegen", &["type:adres"], 2)).unwrap();
+        assert_eq!(suggestions.len(), 2);
+
+        // Request in reverse order, to check that the order is preserved
+        let ids = [suggestions[1].id.as_str(), suggestions[0].id.as_str()];
+        let docs = aw!(client.lookup_many(&ids)).unwrap();
+
+        assert_eq!(docs.len(), 2);
+        assert_eq!(docs[0].id, ids[0]);
+        assert_eq!(docs[1].id, ids[1]);
+    }
+
+    #[test]
+    fn parse_weergavenaam() {
+        let suggestion = |result_type: &str, weergavenaam: &str| SuggestDoc {
+            id: String::new(),
+            result_type: result_type.to_string(),
+            weergavenaam: weergavenaam.to_string(),
+            score: 0.0,
+            status: String::new(),
+        };
+
+        assert_eq!(
+            suggestion("adres", "Castellastraat 26, 6512EX Nijmegen").parse_weergavenaam(),
+            Some(ParsedAddress {
+                straat: "Castellastraat".to_string(),
+                huisnummer: "26".to_string(),
+                postcode: "6512EX".to_string(),
+                woonplaats: "Nijmegen".to_string(),
+            })
+        );
+        assert_eq!(
+            suggestion("adres", "Van Welderenstraat 1A-2, 6511MC Nijmegen")
+                .parse_weergavenaam()
+                .map(|address| (address.straat, address.huisnummer)),
+            Some(("Van Welderenstraat".to_string(), "1A-2".to_string()))
+        );
+        assert_eq!(
+            suggestion("woonplaats", "Nijmegen, Nijmegen, Gelderland").parse_weergavenaam(),
+            None
+        );
+    }
+
+    #[test]
+    fn parse_perceelsleutel() {
+        let sleutel = Perceelsleutel::parse("HTT02-M-5038").unwrap();
+        assert_eq!(sleutel.gemeentecode, "HTT02");
+        assert_eq!(sleutel.sectie, "M");
+        assert_eq!(sleutel.perceelnummer, 5038);
+        assert_eq!(sleutel.index_letter, None);
+        assert_eq!(sleutel.to_string(), "HTT02-M-5038");
+
+        assert_eq!(Perceelsleutel::parse("HTT02 M  5038"), Some(sleutel));
+
+        let apartment = Perceelsleutel::parse("ASD04 F 1234 A1").unwrap();
+        assert_eq!(apartment.index_letter, Some('A'));
+        assert_eq!(apartment.index_nummer, Some(1));
+        assert_eq!(apartment.to_string(), "ASD04-F-1234-A-1");
+        assert_eq!(Perceelsleutel::parse("ASD04-F-1234-A-1"), Some(apartment));
+
+        let whole = Perceelsleutel::parse("ASD04-F-1234-G").unwrap();
+        assert_eq!(whole.index_letter, Some('G'));
+        assert_eq!(whole.index_nummer, None);
+
+        for malformed in [
+            "",
+            "HTT02-M",
+            "HTT2-M-5038",
+            "HTT02-5-5038",
+            "HTT02-M-50X8",
+            "HTT02-M-5038-B",
+            "HTT02-M-5038-A-x",
+        ] {
+            assert_eq!(Perceelsleutel::parse(malformed), None, "{}", malformed);
+        }
+    }
+
+    #[test]
+    fn custom_header() {
+        use wiremock::{matchers, Mock, MockServer};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(matchers::header("X-Gateway-Token", "secret"))
+                .respond_with(empty_response())
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let client = LookupClientBuilder::new()
+                .base_url(&uri)
+                .header("X-Gateway-Token", "secret")
+                .unwrap()
+                .build();
+
+            let result = client.suggest_concrete("6512EX", "26").await;
+            assert!(result.unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn language() {
+        use wiremock::{matchers, Mock, MockServer};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            for language in ["nl", "fy"] {
+                Mock::given(matchers::header("Accept-Language", language))
+                    .respond_with(empty_response())
+                    .expect(1)
+                    .mount(&server)
+                    .await;
+            }
+
+            let uri = server.uri();
+
+            let client = LookupClientBuilder::new().base_url(&uri).build();
+            assert!(client.suggest_concrete("8911AC", "1").await.is_ok());
+
+            let client = LookupClientBuilder::new()
+                .base_url(&uri)
+                .language(Language::Frisian)
+                .build();
+            assert!(client.suggest_concrete("8911AC", "1").await.is_ok());
+        });
+    }
+
+    #[test]
+    fn lookup_with_meta() {
+        use wiremock::{Mock, MockServer};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(wiremock::matchers::any())
+                .respond_with(empty_response().insert_header("X-RateLimit-Remaining", "42"))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let client = LookupClientBuilder::new().base_url(&uri).build();
+
+            let (docs, meta) = client
+                .lookup_with_meta("adr-5826c02550308f6da19e4feb5eb97ec8")
+                .await
+                .unwrap();
+            assert!(docs.is_empty());
+            assert_eq!(meta.status, 200);
+            assert_eq!(meta.rate_limit_remaining(), Some(42));
+
+            let (_, meta) = client
+                .suggest_concrete_with_meta("6512EX", "26")
+                .await
+                .unwrap();
+            assert_eq!(meta.header("x-ratelimit-remaining"), Some("42"));
+        });
+    }
+
+    #[test]
+    fn default_user_agent() {
+        use wiremock::{matchers, Mock, MockServer};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(matchers::header("User-Agent", crate::DEFAULT_USER_AGENT))
+                .respond_with(empty_response())
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let client = LookupClientBuilder::new().base_url(&uri).build();
+
+            let result = client.suggest_concrete("6512EX", "26").await;
+            assert!(result.unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn custom_user_agent() {
+        use wiremock::{matchers, Mock, MockServer};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            let suffixed = format!("myapp/1.2 {}", crate::DEFAULT_USER_AGENT);
+            for user_agent in [suffixed.as_str(), "myapp/1.2"] {
+                Mock::given(matchers::header("User-Agent", user_agent))
+                    .respond_with(empty_response())
+                    .expect(1)
+                    .mount(&server)
+                    .await;
+            }
+
+            let uri = server.uri();
+            let suffixed = LookupClientBuilder::new()
+                .base_url(&uri)
+                .user_agent("myapp/1.2")
+                .build();
+            let plain = LookupClientBuilder::new()
+                .base_url(&uri)
+                .user_agent("myapp/1.2")
+                .user_agent_suffix(false)
+                .build();
+
+            suffixed.suggest_concrete("6512EX", "26").await.unwrap();
+            plain.suggest_concrete("6512EX", "26").await.unwrap();
+        });
+    }
+
+    #[test]
+    fn cloned_clients() {
+        use wiremock::{Mock, MockServer};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(wiremock::matchers::any())
+                .respond_with(empty_response())
+                .expect(2)
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let client = LookupClientBuilder::new().base_url(&uri).build();
+
+            let tasks = [client.clone(), client].map(|client| {
+                tokio::spawn(async move { client.suggest_concrete("6512EX", "26").await })
+            });
+
+            for task in tasks {
+                assert!(task.await.unwrap().unwrap().is_empty());
+            }
+        });
+    }
+
+    #[test]
+    fn disabled_request_timeout() {
+        use wiremock::{Mock, MockServer};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(wiremock::matchers::any())
+                .respond_with(empty_response().set_delay(Duration::from_millis(200)))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let client = LookupClientBuilder::new()
+                .base_url(&uri)
+                .request_timeout_secs(0)
+                .build();
+
+            let result = client.suggest_concrete("6512EX", "26").await;
+            assert!(result.is_ok());
+        });
+    }
+
+    #[test]
+    fn rate_limit_retries() {
+        use std::time::Instant;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            // Throttle the first request only
+            Mock::given(wiremock::matchers::any())
+                .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "1"))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+            Mock::given(wiremock::matchers::any())
+                .respond_with(empty_response())
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let client = LookupClientBuilder::new()
+                .base_url(&uri)
+                .rate_limit_retries(2)
+                .build();
+
+            let start = Instant::now();
+            let result = client.lookup("adr-1").await;
+
+            assert!(result.unwrap().is_empty());
+            assert!(start.elapsed() >= Duration::from_secs(1));
+            assert_eq!(server.received_requests().await.unwrap().len(), 2);
+        });
+    }
+
+    #[test]
+    fn max_retry_after() {
+        use std::time::Instant;
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(wiremock::matchers::any())
+                .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "86400"))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let client = LookupClientBuilder::new()
+                .base_url(&uri)
+                .rate_limit_retries(2)
+                .max_retry_after(Duration::from_secs(10))
+                .build();
+
+            let start = Instant::now();
+            let result = client.lookup("adr-1").await;
+
+            assert!(matches!(
+                result,
+                Err(RateLimited { retry_after }) if retry_after == Duration::from_secs(86400)
+            ));
+            assert!(start.elapsed() < Duration::from_secs(5));
+        });
+    }
+
+    #[test]
+    fn rate_limited() {
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(wiremock::matchers::any())
+                .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "30"))
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let client = LookupClientBuilder::new().base_url(&uri).build();
+
+            assert!(matches!(
+                client.lookup("adr-1").await,
+                Err(RateLimited { retry_after }) if retry_after == Duration::from_secs(30)
+            ));
+        });
+    }
+
+    #[test]
+    fn max_concurrency() {
+        use std::{
+            sync::{Arc, Mutex},
+            time::Instant,
+        };
+        use wiremock::{Mock, MockServer, Request, Respond, ResponseTemplate};
+
+        const DELAY: Duration = Duration::from_millis(200);
+
+        /// Records when every request arrives, each of which is answered after `DELAY`
+        #[derive(Clone)]
+        struct Recorder(Arc<Mutex<Vec<Instant>>>);
+
+        impl Respond for Recorder {
+            fn respond(&self, _: &Request) -> ResponseTemplate {
+                self.0.lock().unwrap().push(Instant::now());
+                empty_response().set_delay(DELAY)
+            }
+        }
+
+        aw!(async {
+            let server = MockServer::start().await;
+            let recorder = Recorder(Arc::default());
+            Mock::given(wiremock::matchers::any())
+                .respond_with(recorder.clone())
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let client = LookupClientBuilder::new()
+                .base_url(&uri)
+                .max_concurrency(2)
+                .build();
+
+            let requests = (0..6).map(|_| client.suggest_concrete("6512EX", "26"));
+            for result in futures::future::join_all(requests).await {
+                result.unwrap();
+            }
+
+            // A request is in flight until DELAY after its arrival
+            let arrivals = recorder.0.lock().unwrap().clone();
+            assert_eq!(arrivals.len(), 6);
+            for arrival in &arrivals {
+                let in_flight = arrivals
+                    .iter()
+                    .filter(|other| *other >= arrival && other.duration_since(*arrival) < DELAY)
+                    .count();
+                assert!(in_flight <= 2, "{in_flight} requests in flight");
+            }
+        });
+    }
+
+    #[test]
+    fn locatieserver_version() {
+        use wiremock::{matchers, Mock, MockServer};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(matchers::path("/locatieserver/search/v3/suggest"))
+                .respond_with(empty_response())
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let client = LookupClientBuilder::new()
+                .base_url(&uri)
+                .locatieserver_version("v3")
+                .build();
+
+            let suggestions = client.suggest_concrete("6512EX", "26").await.unwrap();
+            assert!(suggestions.is_empty());
+        });
+    }
+
+    #[test]
+    fn redirects() {
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(matchers::path("/old/locatieserver/search/v3_1/suggest"))
+                .respond_with(ResponseTemplate::new(301).insert_header(
+                    "Location",
+                    format!("{}/new/locatieserver/search/v3_1/suggest", server.uri()).as_str(),
+                ))
+                .mount(&server)
+                .await;
+            Mock::given(matchers::path("/new/locatieserver/search/v3_1/suggest"))
+                .respond_with(empty_response())
+                .expect(1)
+                .mount(&server)
+                .await;
+
+            let uri = format!("{}/old", server.uri());
+
+            let client = LookupClientBuilder::new().base_url(&uri).build();
+            let result = client.suggest_concrete("6512EX", "26").await;
+            assert!(result.unwrap().is_empty());
+
+            let client = LookupClientBuilder::new()
+                .base_url(&uri)
+                .follow_redirects(false)
+                .build();
+            let result = client.suggest_concrete("6512EX", "26").await;
+            assert!(matches!(result, Err(HttpStatus { code: 301, .. })));
+        });
+    }
+
+    #[test]
+    fn decode_error_snippet() {
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(wiremock::matchers::any())
+                .respond_with(
+                    ResponseTemplate::new(200)
+                        .set_body_json(serde_json::json!({ "response": { "documents": [] } })),
+                )
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let client = LookupClientBuilder::new().base_url(&uri).build();
+
+            let result = client.suggest_concrete("6512EX", "26").await;
+            assert!(matches!(
+                result,
+                Err(DecodeError { body_snippet, .. }) if body_snippet.contains("documents")
+            ));
+        });
+    }
+
+    #[test]
+    fn probe_timeout() {
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(wiremock::matchers::any())
+                .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(5)))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let client = LookupClientBuilder::new().base_url(&uri).build();
+
+            let start = std::time::Instant::now();
+            let result = client.probe(Duration::from_millis(100)).await;
+
+            assert!(matches!(result, Err(Timeout(_))));
+            assert!(start.elapsed() < Duration::from_secs(2));
+        });
+    }
+
+    #[test]
+    fn probe_error_status() {
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            Mock::given(wiremock::matchers::any())
+                .respond_with(ResponseTemplate::new(503))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let client = LookupClientBuilder::new().base_url(&uri).build();
+
+            let result = client.probe(Duration::from_secs(1)).await;
+            assert!(matches!(result, Ok(false)));
+        });
+    }
+
+    #[cfg(feature = "cache")]
+    #[test]
+    fn probe_bypasses_cache() {
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            let office = include_str!("testkit/fixtures/lookup.json");
+            Mock::given(wiremock::matchers::any())
+                .respond_with(ResponseTemplate::new(200).set_body_raw(office, "application/json"))
+                .up_to_n_times(1)
+                .mount(&server)
+                .await;
+            Mock::given(wiremock::matchers::any())
+                .respond_with(ResponseTemplate::new(503))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let client = LookupClientBuilder::new()
+                .base_url(&uri)
+                .cache_capacity(10)
+                .build();
+
+            assert!(client.probe(Duration::from_secs(1)).await.unwrap());
+            assert!(!client.probe(Duration::from_secs(1)).await.unwrap());
+        });
+    }
+
+    #[test]
+    fn invalid_header() {
+        let mut builder = LookupClientBuilder::new();
+
+        assert!(matches!(
+            builder.header("X-Gateway-Token", "line\nbreak"),
+            Err(InvalidHeader(_))
+        ));
+        assert!(matches!(
+            builder.header("invalid header", "value"),
+            Err(InvalidHeader(_))
+        ));
+    }
+
+    #[test]
+    fn solr_response_serde() {
+        let json = serde_json::json!({
+            "response": {
+                "numFound": 1,
+                "start": 0,
+                "maxScore": 7.5,
+                "docs": [{
+                    "id": "adr-1",
+                    "type": "adres",
+                    "weergavenaam": "Castellastraat 26, 6512EX Nijmegen",
+                    "score": 7.5
+                }]
+            }
+        });
+
+        let response: SuggestResponse = serde_json::from_value(json.clone()).unwrap();
+        assert_eq!(response.response.docs[0].result_type, "adres");
+        assert_eq!(serde_json::to_value(&response).unwrap(), json);
+
+        let lookup: LookupResponse<LookupDoc> = serde_json::from_value(serde_json::json!({
+            "response": { "numFound": 1, "docs": [{ "id": "adr-1" }] }
+        }))
+        .unwrap();
+        let reemitted = serde_json::to_string(&lookup).unwrap();
+        let roundtrip: LookupResponse<LookupDoc> = serde_json::from_str(&reemitted).unwrap();
+        assert_eq!(roundtrip, lookup);
+    }
+
+    #[test]
+    fn suggest_did_you_mean() {
+        let client = LookupClientBuilder::new().build();
+
+        let result = aw!(client.suggest("castelastraat nijmgen")).unwrap();
+
+        // The service only offers a collation when its spellchecker has one
+        if let Some(correction) = result.did_you_mean {
+            assert_ne!(correction, "castelastraat nijmgen");
+        }
+    }
+
+    #[test]
+    fn spellcheck_collation() {
+        let response = |spellcheck: serde_json::Value| -> SuggestResponse {
+            serde_json::from_value(serde_json::json!({
+                "response": { "numFound": 0, "docs": [] },
+                "spellcheck": spellcheck
+            }))
+            .unwrap()
+        };
+
+        let plain = response(serde_json::json!({
+            "suggestions": ["nijmgen", { "numFound": 1, "suggestion": ["nijmegen"] }],
+            "collations": ["collation", "castelastraat nijmegen"]
+        }));
+        assert_eq!(
+            plain.did_you_mean().as_deref(),
+            Some("castelastraat nijmegen")
+        );
+
+        let extended = response(serde_json::json!({
+            "collations": ["collation", {
+                "collationQuery": "castellastraat nijmegen",
+                "hits": 40,
+                "misspellingsAndCorrections": ["castelastraat", "castellastraat"]
+            }]
+        }));
+        assert_eq!(
+            extended.did_you_mean().as_deref(),
+            Some("castellastraat nijmegen")
+        );
+
+        assert_eq!(response(serde_json::json!({})).did_you_mean(), None);
+
+        let absent: SuggestResponse =
+            serde_json::from_value(serde_json::json!({ "response": { "docs": [] } })).unwrap();
+        assert_eq!(absent.did_you_mean(), None);
+    }
+
+    #[test]
+    fn lookup_doc_hash() {
+        use std::collections::HashSet;
+
+        let doc = |id: &str, postcode: &str| -> LookupDoc {
+            serde_json::from_value(serde_json::json!({ "id": id, "postcode": postcode })).unwrap()
+        };
+
+        let docs: HashSet<LookupDoc> = [doc("adr-1", "6512EX"), doc("adr-1", "6512 EX")]
+            .into_iter()
+            .collect();
+        assert_eq!(docs.len(), 1);
+    }
+
+    #[test]
+    fn lookup_non_address() {
+        use wiremock::{Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            let body = serde_json::json!({ "response": { "docs": [{
+                "id": "wpl-2bb3ccfe5a4cf1b4a8d5a1d5ec3b5c6e",
+                "type": "woonplaats",
+                "woonplaatsnaam": "Nijmegen"
+            }] } });
+            Mock::given(wiremock::matchers::any())
+                .respond_with(ResponseTemplate::new(200).set_body_json(body))
+                .mount(&server)
+                .await;
+
+            let uri = server.uri();
+            let client = LookupClientBuilder::new().base_url(&uri).build();
+
+            let docs = client
+                .lookup("wpl-2bb3ccfe5a4cf1b4a8d5a1d5ec3b5c6e")
+                .await
+                .unwrap();
+            assert_eq!(docs[0].woonplaatsnaam, "Nijmegen");
+            assert!(docs[0].gekoppeld_perceel.is_empty());
+            assert!(docs[0].percelen().is_empty());
+            assert_eq!(docs[0].nummeraanduiding_id, "");
+        });
+    }
 }
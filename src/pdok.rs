@@ -0,0 +1,313 @@
+//! Queries that combine several PDOK services.
+//!
+//! For example, finding the buildings on a lot requires both the geocoding service
+//! (`lookup`) to find the addresses on the lot, and the BAG to find their buildings.
+use crate::{
+    bag::{BagClient, Pand},
+    brk::{BrkClient, Lot},
+    lookup::{LookupClient, LookupDoc},
+    Error,
+};
+
+use serde::{Deserialize, Serialize};
+
+/// Combines the clients of the individual services.
+#[derive(Clone)]
+pub struct PdokClient {
+    pub lookup: LookupClient,
+    pub bag: BagClient,
+    pub brk: BrkClient,
+}
+
+impl PdokClient {
+    pub fn new(lookup: LookupClient, bag: BagClient, brk: BrkClient) -> Self {
+        Self { lookup, bag, brk }
+    }
+
+    /// Find all addresses on a lot, along with the panden of each address.
+    ///
+    /// All addresses are paged through, see [`LookupClient::addresses_for_lot_stream`],
+    /// and resolved concurrently.
+    pub async fn resolve_lot_addresses(
+        &self,
+        lot_code: &str,
+        lot_letter: &str,
+        lot_number: &str,
+    ) -> Result<Vec<AddressWithBuilding>, Error> {
+        self.resolve_lot_addresses_with_progress(lot_code, lot_letter, lot_number, |_, _| {})
+            .await
+    }
+
+    /// Find all addresses on a lot like [`PdokClient::resolve_lot_addresses`], calling
+    /// `progress` with the number of addresses resolved and the total number of addresses
+    /// as each address completes.
+    pub async fn resolve_lot_addresses_with_progress(
+        &self,
+        lot_code: &str,
+        lot_letter: &str,
+        lot_number: &str,
+        progress: impl Fn(usize, usize),
+    ) -> Result<Vec<AddressWithBuilding>, Error> {
+        use futures::TryStreamExt;
+
+        let suggestions: Vec<_> = self
+            .lookup
+            .addresses_for_lot_stream(lot_code, lot_letter, lot_number)
+            .try_collect()
+            .await?;
+
+        let resolved = suggestions
+            .iter()
+            .map(|suggestion| self.resolve_address(&suggestion.id));
+
+        let addresses = crate::util::try_join_all_with_progress(resolved, progress).await?;
+
+        Ok(addresses.into_iter().flatten().collect())
+    }
+
+    /// Find the lots an address is located on.
+    ///
+    /// Only exact matches of the postal code and housenumber are used, see
+    /// [`LookupClient::lookup_exact`]. Yields no lots when the address does not exist.
+    pub async fn get_lot_for_address(
+        &self,
+        postcode: &str,
+        huisnummer: &str,
+    ) -> Result<Vec<Lot>, Error> {
+        let address = match self.lookup.lookup_exact(postcode, huisnummer).await? {
+            Some(address) => address,
+            None => return Ok(vec![]),
+        };
+
+        let percelen = address.percelen();
+        let lots = percelen.iter().map(|perceel| async move {
+            let perceelnummer = perceel.perceelnummer.to_string();
+            self.brk
+                .get_lot(&perceel.gemeentecode, &perceel.sectie, &perceelnummer)
+                .await
+        });
+
+        let mut lots: Vec<Lot> = futures::future::try_join_all(lots)
+            .await?
+            .into_iter()
+            .flatten()
+            .collect();
+
+        lots.sort();
+        lots.dedup();
+
+        Ok(lots)
+    }
+
+    /// Whether two addresses are located in (at least one of) the same pand, e.g. two
+    /// apartments in the same building.
+    ///
+    /// The panden of both addresses are fetched concurrently. Fails when either of them
+    /// cannot be fetched, rather than reporting different buildings.
+    pub async fn same_building(&self, a: &LookupDoc, b: &LookupDoc) -> Result<bool, Error> {
+        let (panden_a, panden_b) = futures::future::try_join(
+            self.bag.get_panden(&a.adresseerbaarobject_id),
+            self.bag.get_panden(&b.adresseerbaarobject_id),
+        )
+        .await?;
+
+        Ok(panden_a.iter().any(|pand| panden_b.contains(pand)))
+    }
+
+    /// Look up an address id and fetch the panden of its addresseerbaarobject.
+    async fn resolve_address(&self, id: &str) -> Result<Option<AddressWithBuilding>, Error> {
+        let address = match self.lookup.lookup(id).await?.into_iter().next() {
+            Some(address) => address,
+            None => return Ok(None),
+        };
+
+        let panden = self.bag.get_panden(&address.adresseerbaarobject_id).await?;
+
+        Ok(Some(AddressWithBuilding { address, panden }))
+    }
+}
+
+/// An address along with the panden it is located in.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct AddressWithBuilding {
+    pub address: LookupDoc,
+    pub panden: Vec<Pand>,
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use crate::bag::test::get_bag_key;
+    use crate::ClientBuilder;
+    use crate::{bag::BagClientBuilder, brk::BrkClientBuilder, lookup::LookupClientBuilder};
+
+    macro_rules! aw {
+        ($e:expr) => {
+            tokio_test::block_on($e)
+        };
+    }
+
+    fn pdok_client() -> PdokClient {
+        PdokClient::new(
+            LookupClientBuilder::new().build(),
+            BagClientBuilder::new(&get_bag_key()).build(),
+            BrkClientBuilder::new().build(),
+        )
+    }
+
+    #[test]
+    fn test_get_lot_for_address() {
+        let client = pdok_client();
+
+        // TG office
+        let lots = aw!(client.get_lot_for_address("6512EX", "26")).unwrap();
+
+        assert!(lots
+            .iter()
+            .any(|lot| lot.kadastralegemeentecode.as_deref() == Some("HTT02")
+                && lot.sectie.as_deref() == Some("M")
+                && lot.perceelnummer == Some(5038)));
+    }
+
+    #[test]
+    fn test_resolve_lot_addresses() {
+        let client = pdok_client();
+
+        // TG office plot
+        let addresses = aw!(client.resolve_lot_addresses("HTT02", "M", "5038")).unwrap();
+
+        assert!(!addresses.is_empty());
+        assert!(addresses
+            .iter()
+            .any(|address| address.address.postcode == "6512EX" && !address.panden.is_empty()));
+    }
+
+    /// A client using a mock server, with all services at its root
+    fn mock_client(server: &wiremock::MockServer) -> PdokClient {
+        let uri = server.uri();
+
+        PdokClient::new(
+            LookupClientBuilder::new().base_url(&uri).build(),
+            BagClientBuilder::new("key").base_url(&uri).build(),
+            BrkClientBuilder::new().base_url(&uri).build(),
+        )
+    }
+
+    /// Serve a verblijfsobject in the given pand, both based on the testkit fixtures
+    async fn mount_verblijfsobject(server: &wiremock::MockServer, object_id: &str, pand_id: &str) {
+        use wiremock::{matchers, Mock, ResponseTemplate};
+
+        let verblijfsobject = include_str!("testkit/fixtures/verblijfsobject.json")
+            .replace("{{base_url}}", &server.uri())
+            .replace("0268010000084126", object_id)
+            .replace("0268100000317781", pand_id);
+        let pand = include_str!("testkit/fixtures/pand.json").replace("0268100000317781", pand_id);
+
+        Mock::given(matchers::path(format!("/verblijfsobjecten/{object_id}")))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_raw(verblijfsobject, "application/json"),
+            )
+            .mount(server)
+            .await;
+        Mock::given(matchers::path(format!("/panden/{pand_id}")))
+            .respond_with(ResponseTemplate::new(200).set_body_raw(pand, "application/json"))
+            .mount(server)
+            .await;
+    }
+
+    fn address(adresseerbaarobject_id: &str) -> LookupDoc {
+        serde_json::from_value(serde_json::json!({
+            "id": format!("adr-{adresseerbaarobject_id}"),
+            "adresseerbaarobject_id": adresseerbaarobject_id
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_same_building() {
+        use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+        aw!(async {
+            let server = MockServer::start().await;
+            mount_verblijfsobject(&server, "0268010000084126", "0268100000317781").await;
+            mount_verblijfsobject(&server, "0268010000084127", "0268100000317781").await;
+            mount_verblijfsobject(&server, "0268010000099999", "0268100000999999").await;
+            Mock::given(matchers::path("/verblijfsobjecten/0268010000000500"))
+                .respond_with(ResponseTemplate::new(500))
+                .mount(&server)
+                .await;
+
+            let client = mock_client(&server);
+            let office = address("0268010000084126");
+
+            let same = client
+                .same_building(&office, &address("0268010000084127"))
+                .await;
+            assert!(same.unwrap());
+
+            let other = client
+                .same_building(&office, &address("0268010000099999"))
+                .await;
+            assert!(!other.unwrap());
+
+            let failing = client
+                .same_building(&office, &address("0268010000000500"))
+                .await;
+            assert!(matches!(failing, Err(Error::HttpStatus { code: 500, .. })));
+        });
+    }
+
+    #[test]
+    fn test_resolve_lot_addresses_beyond_suggest_limit() {
+        use wiremock::{matchers, Mock, MockServer, Request, ResponseTemplate};
+
+        const ADDRESSES: usize = 12;
+
+        aw!(async {
+            let server = MockServer::start().await;
+            mount_verblijfsobject(&server, "0268010000084126", "0268100000317781").await;
+
+            let docs: Vec<_> = (0..ADDRESSES)
+                .map(|i| {
+                    let id = format!("adr-{i}");
+                    serde_json::json!({ "id": id, "type": "adres", "weergavenaam": id, "score": 1.0 })
+                })
+                .collect();
+            let free = serde_json::json!({ "response": { "numFound": ADDRESSES, "docs": docs } });
+            Mock::given(matchers::path("/locatieserver/search/v3_1/free"))
+                .and(matchers::query_param("q", "gekoppeld_perceel:HTT02-M-5038"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(free))
+                .mount(&server)
+                .await;
+
+            // Every address is located in the TG office
+            Mock::given(matchers::path("/locatieserver/search/v3_1/lookup"))
+                .respond_with(|request: &Request| {
+                    let id = request
+                        .url
+                        .query_pairs()
+                        .find(|(key, _)| key == "id")
+                        .map(|(_, id)| id.into_owned())
+                        .unwrap_or_default();
+                    let body = include_str!("testkit/fixtures/lookup.json")
+                        .replace("adr-5826c02550308f6da19e4feb5eb97ec8", &id);
+
+                    ResponseTemplate::new(200).set_body_raw(body, "application/json")
+                })
+                .mount(&server)
+                .await;
+
+            let client = mock_client(&server);
+            let addresses = client
+                .resolve_lot_addresses("HTT02", "M", "5038")
+                .await
+                .unwrap();
+
+            assert_eq!(addresses.len(), ADDRESSES);
+            assert!(addresses
+                .iter()
+                .all(|a| a.panden[0].identificatiecode == "0268100000317781"));
+        });
+    }
+}
@@ -0,0 +1,182 @@
+//! A mock of the PDOK services, for testing code that uses the clients without hitting the
+//! live services. Enabled by the `testkit` feature.
+//!
+//! [`MockPdok`] serves canned responses for the Tweede Golf office at Castellastraat 26 in
+//! Nijmegen, on lot HTT02-M-5038, for every request:
+//!
+//! ```no_run
+//! # async fn example() {
+//! use pdok_apis::testkit::MockPdok;
+//!
+//! let mock = MockPdok::start().await;
+//! let lots = mock.brk_client().get_lot("HTT02", "M", "5038").await.unwrap();
+//!
+//! assert_eq!(lots[0].perceelnummer, Some(5038));
+//! # }
+//! ```
+//!
+//! Use [`MockPdok::server`] to mount additional responses, which take precedence over the
+//! canned ones.
+
+use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+use crate::{
+    bag::{BagClient, BagClientBuilder},
+    brk::{BrkClient, BrkClientBuilder},
+    lookup::{LookupClient, LookupClientBuilder},
+    pdok::PdokClient,
+    ClientBuilder,
+};
+
+/// Locatieserver suggestions for 6512EX 26
+pub const SUGGEST_FIXTURE: &str = include_str!("testkit/fixtures/suggest.json");
+/// Locatieserver lookup of the TG office address
+pub const LOOKUP_FIXTURE: &str = include_str!("testkit/fixtures/lookup.json");
+/// Locatieserver free query for the addresses on lot HTT02-M-5038
+pub const FREE_FIXTURE: &str = include_str!("testkit/fixtures/free.json");
+/// BAG verblijfsobject of the TG office, linking to its pand through `{{base_url}}`
+pub const VERBLIJFSOBJECT_FIXTURE: &str = include_str!("testkit/fixtures/verblijfsobject.json");
+/// BAG pand of the TG office, in rijksdriehoek coordinates
+pub const PAND_FIXTURE: &str = include_str!("testkit/fixtures/pand.json");
+/// Kadastrale kaart perceel HTT02-M-5038, in WGS84 coordinates
+pub const PERCEEL_FIXTURE: &str = include_str!("testkit/fixtures/perceel.json");
+
+/// The canned responses rank below mocks mounted with the default priority (5)
+const CANNED_PRIORITY: u8 = 10;
+
+/// A mock server serving the fixtures of all PDOK services.
+pub struct MockPdok {
+    server: MockServer,
+}
+
+impl MockPdok {
+    /// Start a mock server on a random local port.
+    pub async fn start() -> Self {
+        let server = MockServer::start().await;
+        let bag_url = format!("{}/bag", server.uri());
+
+        let routes = [
+            ("/locatieserver/search/v3_1/suggest", SUGGEST_FIXTURE),
+            ("/locatieserver/search/v3_1/lookup", LOOKUP_FIXTURE),
+            ("/locatieserver/search/v3_1/free", FREE_FIXTURE),
+            ("/brk", PERCEEL_FIXTURE),
+        ];
+
+        for (path, fixture) in routes {
+            Mock::given(matchers::path(path))
+                .respond_with(json_response(fixture))
+                .with_priority(CANNED_PRIORITY)
+                .mount(&server)
+                .await;
+        }
+
+        Mock::given(matchers::path_regex("^/bag/verblijfsobjecten/"))
+            .respond_with(json_response(
+                &VERBLIJFSOBJECT_FIXTURE.replace("{{base_url}}", &bag_url),
+            ))
+            .with_priority(CANNED_PRIORITY)
+            .mount(&server)
+            .await;
+        Mock::given(matchers::path_regex("^/bag/panden/"))
+            .respond_with(json_response(PAND_FIXTURE))
+            .with_priority(CANNED_PRIORITY)
+            .mount(&server)
+            .await;
+
+        MockPdok { server }
+    }
+
+    /// The underlying server, e.g. to mount additional responses or inspect the requests.
+    pub fn server(&self) -> &MockServer {
+        &self.server
+    }
+
+    /// The base url of the locatieserver mock.
+    pub fn lookup_url(&self) -> String {
+        self.server.uri()
+    }
+
+    /// The base url of the BAG mock.
+    pub fn bag_url(&self) -> String {
+        format!("{}/bag", self.server.uri())
+    }
+
+    /// The base url of the kadastrale kaart WFS mock.
+    pub fn brk_url(&self) -> String {
+        format!("{}/brk", self.server.uri())
+    }
+
+    /// A locatieserver client using the mock.
+    pub fn lookup_client(&self) -> LookupClient {
+        LookupClientBuilder::new()
+            .base_url(&self.lookup_url())
+            .build()
+    }
+
+    /// A BAG client using the mock, with a dummy API key.
+    pub fn bag_client(&self) -> BagClient {
+        BagClientBuilder::new("testkit")
+            .base_url(&self.bag_url())
+            .build()
+    }
+
+    /// A kadastrale kaart client using the mock.
+    pub fn brk_client(&self) -> BrkClient {
+        BrkClientBuilder::new().base_url(&self.brk_url()).build()
+    }
+
+    /// A client combining all services, using the mock.
+    pub fn pdok_client(&self) -> PdokClient {
+        PdokClient::new(self.lookup_client(), self.bag_client(), self.brk_client())
+    }
+}
+
+fn json_response(body: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_raw(body.to_owned(), "application/json")
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    macro_rules! aw {
+        ($e:expr) => {
+            tokio_test::block_on($e)
+        };
+    }
+
+    #[test]
+    fn test_get_lot() {
+        aw!(async {
+            let mock = MockPdok::start().await;
+
+            let lots = mock
+                .brk_client()
+                .get_lot("HTT02", "M", "5038")
+                .await
+                .unwrap();
+
+            assert_eq!(lots.len(), 1);
+            assert_eq!(lots[0].kadastralegemeentecode.as_deref(), Some("HTT02"));
+            assert_eq!(lots[0].perceelnummer, Some(5038));
+        });
+    }
+
+    #[test]
+    fn test_resolve_lot_addresses() {
+        aw!(async {
+            let mock = MockPdok::start().await;
+
+            let addresses = mock
+                .pdok_client()
+                .resolve_lot_addresses("HTT02", "M", "5038")
+                .await
+                .unwrap();
+
+            assert_eq!(addresses.len(), 1);
+            assert_eq!(addresses[0].address.postcode, "6512EX");
+            assert_eq!(addresses[0].panden[0].bouwjaar, "1925");
+        });
+    }
+}
@@ -0,0 +1,404 @@
+//! Request handling shared by all clients.
+
+use crate::Error::{self, *};
+
+use reqwest::{header::HeaderMap, Client, IntoUrl, Request, RequestBuilder, Response};
+use serde::de::DeserializeOwned;
+use std::{num::NonZeroUsize, sync::Arc, time::Duration};
+use tokio::sync::Semaphore;
+
+#[cfg(feature = "cache")]
+use crate::cache::ResponseCache;
+
+/// Create a `reqwest::ClientBuilder` with the options that all builders share.
+///
+/// On wasm, requests go through the browser's fetch API, which handles connecting and
+/// redirects itself, so the timeouts and redirect policy are ignored there.
+#[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+pub(crate) fn client_builder(
+    user_agent: &str,
+    user_agent_suffix: bool,
+    headers: HeaderMap,
+    connection_timeout_secs: u64,
+    request_timeout_secs: u64,
+    follow_redirects: bool,
+) -> reqwest::ClientBuilder {
+    // Identify the crate after a custom user agent, unless that is disabled
+    let user_agent = if user_agent_suffix && user_agent != crate::DEFAULT_USER_AGENT {
+        format!("{} {}", user_agent, crate::DEFAULT_USER_AGENT)
+    } else {
+        user_agent.to_string()
+    };
+
+    let client_builder = reqwest::ClientBuilder::new()
+        .user_agent(user_agent)
+        .default_headers(headers);
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let client_builder = {
+        let mut client_builder =
+            client_builder.connect_timeout(Duration::from_secs(connection_timeout_secs));
+
+        // A request timeout of zero disables the timeout altogether
+        if request_timeout_secs > 0 {
+            client_builder = client_builder.timeout(Duration::new(request_timeout_secs, 0));
+        }
+
+        if !follow_redirects {
+            client_builder = client_builder.redirect(reqwest::redirect::Policy::none());
+        }
+
+        // Takes precedence when native TLS is enabled as well
+        #[cfg(feature = "rustls-tls")]
+        {
+            client_builder = client_builder.use_rustls_tls();
+        }
+
+        client_builder
+    };
+
+    client_builder
+}
+
+/// Override the timeout of a single request, which is not supported (and ignored) on wasm.
+#[cfg_attr(target_arch = "wasm32", allow(unused_variables))]
+pub(crate) fn with_timeout(request: RequestBuilder, timeout: Duration) -> RequestBuilder {
+    #[cfg(not(target_arch = "wasm32"))]
+    let request = request.timeout(timeout);
+
+    request
+}
+
+/// A response body that is read chunk by chunk, as it arrives.
+///
+/// Responses cannot be read incrementally on wasm, so there the body is a single chunk.
+pub(crate) struct BodyChunks(Option<Response>);
+
+impl BodyChunks {
+    pub(crate) fn new(response: Response) -> Self {
+        BodyChunks(Some(response))
+    }
+
+    /// The next chunk of the body, or `None` when it has been read completely.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub(crate) async fn next(&mut self) -> Result<Option<impl AsRef<[u8]>>, reqwest::Error> {
+        match &mut self.0 {
+            Some(response) => response.chunk().await,
+            None => Ok(None),
+        }
+    }
+
+    /// The next chunk of the body, or `None` when it has been read completely.
+    #[cfg(target_arch = "wasm32")]
+    pub(crate) async fn next(&mut self) -> Result<Option<impl AsRef<[u8]>>, reqwest::Error> {
+        match self.0.take() {
+            Some(response) => response.bytes().await.map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+/// A `reqwest::Client` along with the behavior that applies to every request.
+#[derive(Clone)]
+pub(crate) struct Transport {
+    client: Client,
+    /// Bounds the number of requests in flight, shared by all clones
+    limit: Option<Arc<Semaphore>>,
+    /// How often a request is retried after a 429 Too Many Requests
+    rate_limit_retries: u32,
+    /// The longest `Retry-After` that is waited out, longer waits fail right away
+    max_retry_after: Duration,
+    #[cfg(feature = "cache")]
+    cache: Option<Arc<ResponseCache>>,
+}
+
+impl Transport {
+    pub(crate) fn new(client: Client) -> Self {
+        Transport {
+            client,
+            limit: None,
+            rate_limit_retries: 0,
+            max_retry_after: Duration::ZERO,
+            #[cfg(feature = "cache")]
+            cache: None,
+        }
+    }
+
+    /// Cache successful responses, keyed by their url.
+    /// A capacity of zero disables the cache.
+    #[cfg(feature = "cache")]
+    pub(crate) fn with_cache(mut self, capacity: usize, ttl: Option<std::time::Duration>) -> Self {
+        self.cache =
+            NonZeroUsize::new(capacity).map(|capacity| Arc::new(ResponseCache::new(capacity, ttl)));
+        self
+    }
+
+    /// Send at most `max_concurrency` requests at a time, waiting for a request to finish
+    /// before sending the next. `None` leaves the number of requests unbounded.
+    pub(crate) fn with_max_concurrency(mut self, max_concurrency: Option<NonZeroUsize>) -> Self {
+        self.limit = max_concurrency.map(|permits| Arc::new(Semaphore::new(permits.get())));
+        self
+    }
+
+    /// Wait out a 429 Too Many Requests and retry, up to `retries` times per request,
+    /// unless the service asks to wait longer than `max_retry_after`.
+    ///
+    /// There is no timer to wait with on wasm, so there rate limits always fail.
+    pub(crate) fn with_rate_limit_retries(
+        mut self,
+        retries: u32,
+        max_retry_after: Duration,
+    ) -> Self {
+        if cfg!(not(target_arch = "wasm32")) {
+            self.rate_limit_retries = retries;
+            self.max_retry_after = max_retry_after;
+        }
+        self
+    }
+
+    pub(crate) fn get<U: IntoUrl>(&self, url: U) -> RequestBuilder {
+        self.client.get(url)
+    }
+
+    /// Send a request, turning error statuses into [`Error::HttpStatus`] and
+    /// [`Error::RateLimited`].
+    pub(crate) async fn send(&self, request: RequestBuilder) -> Result<Response, Error> {
+        let request = request.build()?;
+
+        #[cfg(feature = "cache")]
+        let key = request.url().to_string();
+
+        #[cfg(feature = "cache")]
+        if let Some(cache) = &self.cache {
+            if let Some(response) = cache.get(&key) {
+                return Ok(response);
+            }
+        }
+
+        let response = self.execute(request).await?;
+
+        #[cfg(feature = "cache")]
+        if let Some(cache) = &self.cache {
+            return Ok(cache.store(key, response).await?);
+        }
+
+        Ok(response)
+    }
+
+    /// Send a health check, bypassing the cache so that a stored response cannot hide an
+    /// outage. An error status yields `None`, other failures are errors.
+    pub(crate) async fn probe(&self, request: RequestBuilder) -> Result<Option<Response>, Error> {
+        match self.execute(request.build()?).await {
+            Ok(response) => Ok(Some(response)),
+            Err(HttpStatus { .. }) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Execute a request, waiting out rate limits and checking the status
+    async fn execute(&self, mut request: Request) -> Result<Response, Error> {
+        let mut retries_left = self.rate_limit_retries;
+
+        loop {
+            // Requests without a body, i.e. all of ours, can always be cloned
+            let retry = match retries_left {
+                0 => None,
+                _ => request.try_clone(),
+            };
+
+            // The semaphore is never closed, so acquiring only fails when there is no limit
+            let permit = match &self.limit {
+                Some(limit) => limit.acquire().await.ok(),
+                None => None,
+            };
+
+            let url = request.url().clone();
+            let result = match self.client.execute(request).await {
+                Ok(response) => check_status(response).await,
+                Err(error) => Err(error.into()),
+            };
+
+            drop(permit);
+
+            match (result, retry) {
+                (Err(RateLimited { retry_after }), Some(retry))
+                    if retry_after <= self.max_retry_after =>
+                {
+                    log::warn!("{} is rate limited, retrying in {:?}", url, retry_after);
+                    retries_left -= 1;
+
+                    #[cfg(not(target_arch = "wasm32"))]
+                    tokio::time::sleep(retry_after).await;
+
+                    request = retry;
+                }
+                (result, _) => return result,
+            }
+        }
+    }
+
+    /// Send a request and decode its JSON body, see [`decode_json`].
+    pub(crate) async fn send_json<T: DeserializeOwned>(
+        &self,
+        request: RequestBuilder,
+    ) -> Result<T, Error> {
+        let response = self.send(request).await?;
+
+        decode_json(response).await
+    }
+
+    /// Fetch a url without further options and decode its JSON body.
+    pub(crate) async fn get_json<T: DeserializeOwned, U: IntoUrl>(
+        &self,
+        url: U,
+    ) -> Result<T, Error> {
+        self.send_json(self.get(url)).await
+    }
+}
+
+/// The maximum number of bytes of a body that is kept in an [`Error::DecodeError`]
+const BODY_SNIPPET_LEN: usize = 500;
+
+/// Decode a JSON body, keeping the start of the body in the error when it does not match.
+pub(crate) async fn decode_json<T: DeserializeOwned>(response: Response) -> Result<T, Error> {
+    let body = response.text().await?;
+
+    serde_json::from_str(&body).map_err(|source| {
+        let mut end = body.len().min(BODY_SNIPPET_LEN);
+        while !body.is_char_boundary(end) {
+            end -= 1;
+        }
+
+        DecodeError {
+            source,
+            body_snippet: body[..end].to_string(),
+        }
+    })
+}
+
+/// The time to wait after a 429 Too Many Requests without a valid `Retry-After` header
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(1);
+
+/// Parse a `Retry-After` header, which is either a number of seconds or an HTTP date.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+
+    // A date in the past means the request can be retried right away
+    Some(
+        (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
+
+/// Turn a response with an error status into an [`Error::HttpStatus`], or an
+/// [`Error::RateLimited`] for 429 Too Many Requests
+///
+/// Redirects only get here when following them is disabled on the builder.
+async fn check_status(response: Response) -> Result<Response, Error> {
+    let status = response.status();
+
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(parse_retry_after)
+            .unwrap_or(DEFAULT_RETRY_AFTER);
+
+        Err(RateLimited { retry_after })
+    } else if status.is_redirection() {
+        let location = response.headers().get(reqwest::header::LOCATION);
+        log::warn!("{} redirected to {:?}", response.url(), location);
+
+        Err(HttpStatus {
+            code: status.as_u16(),
+            body: String::new(),
+        })
+    } else if status.is_client_error() || status.is_server_error() {
+        let body = response.text().await.unwrap_or_default();
+        Err(HttpStatus {
+            code: status.as_u16(),
+            body,
+        })
+    } else {
+        Ok(response)
+    }
+}
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+    use wiremock::{matchers, Mock, MockServer, ResponseTemplate};
+
+    macro_rules! aw {
+        ($e:expr) => {
+            tokio_test::block_on($e)
+        };
+    }
+
+    #[derive(serde::Deserialize, Debug)]
+    struct Doc {
+        #[allow(dead_code)]
+        id: String,
+    }
+
+    #[test]
+    fn get_json_errors() {
+        aw!(async {
+            let server = MockServer::start().await;
+            for (path, body) in [("/doc", r#"{"id": "a"}"#), ("/other", r#"{"name": "a"}"#)] {
+                Mock::given(matchers::path(path))
+                    .respond_with(ResponseTemplate::new(200).set_body_string(body))
+                    .mount(&server)
+                    .await;
+            }
+            Mock::given(matchers::path("/broken"))
+                .respond_with(ResponseTemplate::new(500).set_body_string("oops"))
+                .mount(&server)
+                .await;
+
+            let transport = Transport::new(Client::new());
+            let uri = server.uri();
+
+            let doc: Result<Doc, Error> = transport.get_json(format!("{uri}/doc")).await;
+            assert!(doc.is_ok());
+
+            let mismatch: Result<Doc, Error> = transport.get_json(format!("{uri}/other")).await;
+            assert!(matches!(
+                mismatch,
+                Err(DecodeError { body_snippet, .. }) if body_snippet.contains("name")
+            ));
+
+            let status: Result<Doc, Error> = transport.get_json(format!("{uri}/broken")).await;
+            assert!(matches!(status, Err(HttpStatus { code: 500, body }) if body == "oops"));
+
+            // Nothing listens on the discard port
+            let network: Result<Doc, Error> = transport.get_json("http://127.0.0.1:9").await;
+            assert!(matches!(network, Err(NetworkProblem(_))));
+        });
+    }
+
+    #[test]
+    fn retry_after() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+        assert_eq!(parse_retry_after(" 1 "), Some(Duration::from_secs(1)));
+        assert_eq!(
+            parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(Duration::ZERO)
+        );
+
+        let in_a_minute = (chrono::Utc::now() + chrono::Duration::seconds(60)).to_rfc2822();
+        let wait = parse_retry_after(&in_a_minute).unwrap();
+        assert!(wait > Duration::from_secs(55) && wait <= Duration::from_secs(60));
+
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+}
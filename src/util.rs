@@ -45,6 +45,24 @@ pub fn bbox_to_linestring(bbox: Rect<f64>) -> Result<geojson::Value, ()> {
     Ok(geojson::Value::from(polygon.exterior()))
 }
 
+/// The GeoJSON `bbox` member of a Rect: `[min x, min y, max x, max y]`.
+pub fn rect_to_bbox_array(rect: Rect<f64>) -> geojson::Bbox {
+    vec![rect.min().x, rect.min().y, rect.max().x, rect.max().y]
+}
+
+/// Parse a GeoJSON `bbox` member, either 2D (4 elements) or 3D (6 elements, `[min x, min y,
+/// min z, max x, max y, max z]`) in which case the z coordinates are ignored.
+pub fn bbox_array_to_rect(bbox: &[f64]) -> Option<Rect<f64>> {
+    let (min, max) = match *bbox {
+        [min_x, min_y, max_x, max_y] | [min_x, min_y, _, max_x, max_y, _] => {
+            ((min_x, min_y), (max_x, max_y))
+        }
+        _ => return None,
+    };
+
+    Some(Rect::new(Coord::from(min), Coord::from(max)))
+}
+
 /// Return coordinate with easting (longitude) in x and northing (latitude) in y
 pub fn coordinate_rijksdriehoek_to_wgs84(rd_x: f64, rd_y: f64) -> Coord<f64> {
     // Latitude is y and longitude is x
@@ -52,6 +70,226 @@ pub fn coordinate_rijksdriehoek_to_wgs84(rd_x: f64, rd_y: f64) -> Coord<f64> {
     Coord { x, y }
 }
 
+/// Return the rijksdriehoek coordinate of a WGS84 position.
+///
+/// Note the argument order: longitude (x) first, latitude (y) second,
+/// whereas the underlying `rijksdriehoek` crate takes latitude first.
+pub fn coordinate_wgs84_to_rijksdriehoek(lon: f64, lat: f64) -> Coord<f64> {
+    let (x, y) = rijksdriehoek::wgs84_to_rijksdriehoek(lat, lon);
+    Coord { x, y }
+}
+
+/// Whether a coordinate falls within the bounds of the rijksdriehoek grid,
+/// e.g. to catch rijksdriehoek coordinates that are passed where WGS84 is expected.
+///
+/// WGS84 coordinates never fall within these bounds, so the check is unambiguous.
+pub fn looks_like_rijksdriehoek(coord: Coord<f64>) -> bool {
+    (7_000.0..=300_000.0).contains(&coord.x) && (289_000.0..=629_000.0).contains(&coord.y)
+}
+
+/// Great-circle distance in meters between two WGS84 points, with longitude in x and latitude in y.
+pub fn haversine_distance(a: Point<f64>, b: Point<f64>) -> f64 {
+    use geo::algorithm::haversine_distance::HaversineDistance;
+
+    a.haversine_distance(&b)
+}
+
+/// Compass bearing in degrees (0–360, north is 0, east is 90) of the great circle from one
+/// WGS84 point towards another, with longitude in x and latitude in y.
+pub fn bearing(from: Point<f64>, to: Point<f64>) -> f64 {
+    use geo::algorithm::haversine_bearing::HaversineBearing;
+
+    from.haversine_bearing(to).rem_euclid(360.0)
+}
+
+/// Area in m² of a polygon in rijksdriehoek coordinates.
+///
+/// Rijksdriehoek coordinates are in meters, so the planar area is valid;
+/// do not use this for WGS84 polygons.
+pub fn planar_area_m2(poly: &Polygon<f64>) -> f64 {
+    use geo::algorithm::area::Area;
+
+    poly.unsigned_area()
+}
+
+/// Reduce the number of vertices of a geometry using the Douglas–Peucker algorithm,
+/// e.g. before rendering detailed perceel outlines in a browser.
+///
+/// `epsilon` is in the units of the geometry's coordinate space, i.e. meters for
+/// rijksdriehoek and degrees for WGS84. Each ring of a polygon is simplified separately.
+/// Points are returned unchanged.
+pub fn simplify_geometry(geom: &geo::Geometry<f64>, epsilon: f64) -> geo::Geometry<f64> {
+    use geo::algorithm::simplify::Simplify;
+    use geo::Geometry;
+
+    match geom {
+        Geometry::LineString(line) => Geometry::LineString(line.simplify(&epsilon)),
+        Geometry::MultiLineString(lines) => Geometry::MultiLineString(lines.simplify(&epsilon)),
+        Geometry::Polygon(polygon) => Geometry::Polygon(polygon.simplify(&epsilon)),
+        Geometry::MultiPolygon(polygons) => Geometry::MultiPolygon(polygons.simplify(&epsilon)),
+        Geometry::GeometryCollection(collection) => Geometry::GeometryCollection(
+            collection
+                .iter()
+                .map(|geom| simplify_geometry(geom, epsilon))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Round every coordinate of a geometry to the given number of decimals, e.g. to shrink
+/// GeoJSON sent to a browser.
+///
+/// 6 decimals are about 10 cm in WGS84, 2 decimals are a centimeter in rijksdriehoek.
+pub fn round_geometry_coords(geom: &geo::Geometry<f64>, decimals: u8) -> geo::Geometry<f64> {
+    use geo::algorithm::map_coords::MapCoords;
+
+    let factor = 10f64.powi(decimals.into());
+    let round = |value: f64| (value * factor).round() / factor;
+
+    geom.map_coords(|coord| Coord {
+        x: round(coord.x),
+        y: round(coord.y),
+    })
+}
+
+/// Merge polygons into their combined outline, e.g. the percelen of a single landholding.
+///
+/// Overlapping and touching polygons are joined, while disjoint ones remain separate
+/// parts of the result. Returns `None` if there are no polygons.
+pub fn union_polygons(polygons: &[Polygon<f64>]) -> Option<MultiPolygon<f64>> {
+    use geo::algorithm::bool_ops::BooleanOps;
+
+    fold_first(
+        polygons
+            .iter()
+            .map(|polygon| MultiPolygon::new(vec![polygon.clone()])),
+        |acc, polygon| acc.union(&polygon),
+    )
+}
+
+/// Merge the geometries of lots into their combined outline, see [`union_polygons`].
+///
+/// Lots without a (multi)polygon geometry are skipped. All lots should be in the same
+/// coordinate space.
+pub fn union_lots(lots: &[crate::brk::Lot]) -> Option<MultiPolygon<f64>> {
+    let polygons: Vec<Polygon<f64>> = lots
+        .iter()
+        .filter_map(|lot| crate::geometry::geojson_value_to_multipolygon(&lot.geometry.value).ok())
+        .flat_map(|multi_polygon| multi_polygon.0)
+        .collect();
+
+    union_polygons(&polygons)
+}
+
+/// A polygon that would give unreliable results for e.g. `Area` and `Contains`
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeometryValidationError {
+    /// The first and last coordinate of a ring differ
+    UnclosedRing,
+    /// A ring has fewer than the 4 coordinates (3 distinct) needed to enclose an area
+    TooFewVertices(usize),
+    /// Two edges of a ring cross or touch
+    SelfIntersection,
+    /// The geometry is not a (multi)polygon, or could not be converted
+    NotAPolygon,
+}
+
+/// Check that every ring of a polygon is closed, has enough vertices and does not
+/// intersect itself.
+///
+/// Note that `geo` closes rings when constructing a polygon, so rings that were unclosed
+/// in the source data are only caught by [`validate_geojson_polygon`].
+/// Intersections between different rings are not checked.
+pub fn validate_polygon(polygon: &Polygon<f64>) -> Result<(), GeometryValidationError> {
+    std::iter::once(polygon.exterior())
+        .chain(polygon.interiors())
+        .try_for_each(validate_ring)
+}
+
+/// Validate a GeoJSON polygon or multipolygon as received, see [`validate_polygon`].
+pub fn validate_geojson_polygon(value: &geojson::Value) -> Result<(), GeometryValidationError> {
+    use geojson::Value;
+
+    let rings: Vec<&Vec<geojson::Position>> = match value {
+        Value::Polygon(rings) => rings.iter().collect(),
+        Value::MultiPolygon(polygons) => polygons.iter().flatten().collect(),
+        _ => return Err(GeometryValidationError::NotAPolygon),
+    };
+
+    for ring in rings {
+        if ring.len() < 4 {
+            return Err(GeometryValidationError::TooFewVertices(ring.len()));
+        }
+        if ring.first() != ring.last() {
+            return Err(GeometryValidationError::UnclosedRing);
+        }
+    }
+
+    crate::geometry::geojson_value_to_multipolygon(value)
+        .map_err(|_| GeometryValidationError::NotAPolygon)?
+        .iter()
+        .try_for_each(validate_polygon)
+}
+
+fn validate_ring(ring: &geo::LineString<f64>) -> Result<(), GeometryValidationError> {
+    use geo::algorithm::line_intersection::line_intersection;
+
+    if ring.0.len() < 4 {
+        return Err(GeometryValidationError::TooFewVertices(ring.0.len()));
+    }
+    if !ring.is_closed() {
+        return Err(GeometryValidationError::UnclosedRing);
+    }
+
+    let lines: Vec<_> = ring.lines().collect();
+    let last = lines.len() - 1;
+
+    for (i, a) in lines.iter().enumerate() {
+        // Adjacent edges share a vertex, including the first and last edge
+        for (j, b) in lines.iter().enumerate().skip(i + 2) {
+            if i == 0 && j == last {
+                continue;
+            }
+            if line_intersection(*a, *b).is_some() {
+                return Err(GeometryValidationError::SelfIntersection);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Await all futures concurrently like `futures::future::try_join_all`, calling `progress`
+/// with the number of completed futures and the total each time one completes.
+pub(crate) async fn try_join_all_with_progress<F, T, E>(
+    futures: impl IntoIterator<Item = F>,
+    progress: impl Fn(usize, usize),
+) -> Result<Vec<T>, E>
+where
+    F: std::future::Future<Output = Result<T, E>>,
+{
+    use futures::stream::{FuturesUnordered, StreamExt};
+
+    let mut pending: FuturesUnordered<_> = futures
+        .into_iter()
+        .enumerate()
+        .map(|(index, future)| async move { (index, future.await) })
+        .collect();
+
+    let total = pending.len();
+    let mut results: Vec<Option<T>> = (0..total).map(|_| None).collect();
+    let mut done = 0;
+
+    while let Some((index, result)) = pending.next().await {
+        results[index] = Some(result?);
+        done += 1;
+        progress(done, total);
+    }
+
+    Ok(results.into_iter().flatten().collect())
+}
+
 /// Merge two bboxes to a single bbox.
 pub fn merge_bboxes(acc: Rect<f64>, r: Rect<f64>) -> Rect<f64> {
     Rect::new(
@@ -66,23 +304,23 @@ pub fn merge_bboxes(acc: Rect<f64>, r: Rect<f64>) -> Rect<f64> {
     )
 }
 
-/// Stretch a Rect to a square.
+/// Stretch a Rect to a square, around the same center.
 pub fn stretch_to_square(rect: Rect<f64>) -> Rect<f64> {
     use geo::algorithm::centroid::Centroid;
 
-    let (height, width) = (rect.height(), rect.width());
-    let (hheight, hwidth) = (height / 2., width / 2.);
+    let half_side = rect.height().max(rect.width()) / 2.;
     let centroid = rect.centroid();
 
-    if rect.height() < rect.width() {
-        rect.min().y = centroid.y() - hwidth;
-        rect.max().y = centroid.y() + hwidth;
-    } else {
-        rect.min().x = centroid.x() - hheight;
-        rect.max().x = centroid.x() + hheight;
-    }
-
-    rect
+    Rect::new(
+        Coord {
+            x: centroid.x() - half_side,
+            y: centroid.y() - half_side,
+        },
+        Coord {
+            x: centroid.x() + half_side,
+            y: centroid.y() + half_side,
+        },
+    )
 }
 
 /// Add a margin to both sides of the Rect.
@@ -92,44 +330,431 @@ pub fn add_margin(rect: Rect<f64>, margin: f64) -> Rect<f64> {
     geo::Rect::new(min - margin, max + margin)
 }
 
-/// Expand the bounding box to the given size (height and width)
-/// Note: only works with rijksdriehoekscoordinates, will probably panic otherwise
-pub fn expand_to_size(rect: Rect<f64>, size: f64) -> Rect<f64> {
-    // Make sure the rect to a square
-    let square_bbox = stretch_to_square(rect);
+/// A square bounding box around the geometry for showing it on a map, with padding on
+/// every side of `padding_fraction` times the side of the square, e.g. `0.1` for 10%.
+///
+/// Yields `None` for an empty geometry. A single point yields a square without size.
+pub fn display_bbox(geom: &geo::Geometry<f64>, padding_fraction: f64) -> Option<Rect<f64>> {
+    use geo::algorithm::bounding_rect::BoundingRect;
 
-    // Determine how much margin should be added
-    let width = square_bbox.max().y - square_bbox.min().y;
-    let margin = (size - width) / 2.0;
+    let square = stretch_to_square(geom.bounding_rect()?);
+    let padding = square.width() * padding_fraction.max(0.0);
 
-    // Return the margin
-    add_margin(square_bbox, margin)
+    Some(add_margin(square, padding))
 }
 
-pub fn points_to_geojson_multipoint(points: Vec<Point<f64>>) -> geojson::GeoJson {
-    let mp: MultiPoint<f64> = points.into();
-    let geometry = geojson::Geometry::new(geojson::Value::from(&mp));
+/// Why [`expand_to_size`] rejected its arguments
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvalidSize {
+    /// The bounding box has a coordinate that is NaN or infinite
+    NonFiniteBbox,
+    /// The size is NaN or infinite
+    NonFiniteSize,
+    /// The size is negative
+    NegativeSize,
+}
 
-    geojson::Feature {
-        bbox: None,
-        geometry: Some(geometry),
-        id: None,
-        properties: None,
-        foreign_members: None,
+impl std::fmt::Display for InvalidSize {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InvalidSize::NonFiniteBbox => write!(f, "bounding box is not finite"),
+            InvalidSize::NonFiniteSize => write!(f, "size is not finite"),
+            InvalidSize::NegativeSize => write!(f, "size is negative"),
+        }
     }
-    .into()
 }
 
-pub fn polygons_to_geojson_multipolygon(polygons: Vec<Polygon<f64>>) -> geojson::GeoJson {
+impl std::error::Error for InvalidSize {}
+
+/// Expand the bounding box to a square of at least the given size (height and width).
+///
+/// The size is in the units of the coordinate space, so this is meant for rijksdriehoek
+/// coordinates where it is in meters; in WGS84 a square in degrees is not square on the map.
+/// A bounding box that is already larger is stretched to a square, but never shrunk.
+pub fn expand_to_size(rect: Rect<f64>, size: f64) -> Result<Rect<f64>, InvalidSize> {
+    let finite = [rect.min(), rect.max()]
+        .iter()
+        .all(|coord| coord.x.is_finite() && coord.y.is_finite());
+
+    if !finite {
+        return Err(InvalidSize::NonFiniteBbox);
+    }
+    if !size.is_finite() {
+        return Err(InvalidSize::NonFiniteSize);
+    }
+    if size < 0.0 {
+        return Err(InvalidSize::NegativeSize);
+    }
+
+    let square_bbox = stretch_to_square(rect);
+    let margin = (size - square_bbox.width()) / 2.0;
+
+    Ok(add_margin(square_bbox, margin.max(0.0)))
+}
+
+/// A feature of a MultiPoint of the points.
+///
+/// With `include_bbox` the `bbox` member is set to the extent of the points, e.g. for
+/// tile servers that use it for culling.
+pub fn points_to_geojson_multipoint(
+    points: Vec<Point<f64>>,
+    include_bbox: bool,
+) -> geojson::GeoJson {
+    let mp: MultiPoint<f64> = points.into();
+    geometry_to_feature(&mp.into(), include_bbox)
+}
+
+/// A feature of a MultiPolygon of the polygons.
+///
+/// With `include_bbox` the `bbox` member is set to the extent of the polygons.
+pub fn polygons_to_geojson_multipolygon(
+    polygons: Vec<Polygon<f64>>,
+    include_bbox: bool,
+) -> geojson::GeoJson {
     let mp: MultiPolygon<f64> = polygons.into();
-    let geometry = geojson::Geometry::new(geojson::Value::from(&mp));
+    geometry_to_feature(&mp.into(), include_bbox)
+}
+
+fn geometry_to_feature(geometry: &geo::Geometry<f64>, include_bbox: bool) -> geojson::GeoJson {
+    use geo::algorithm::bounding_rect::BoundingRect;
+
+    let bbox = if include_bbox {
+        geometry.bounding_rect().map(rect_to_bbox_array)
+    } else {
+        None
+    };
 
     geojson::Feature {
-        bbox: None,
-        geometry: Some(geometry),
+        bbox,
+        geometry: Some(geojson::Geometry::new(geojson::Value::from(geometry))),
         id: None,
         properties: None,
         foreign_members: None,
     }
     .into()
 }
+
+#[cfg(test)]
+mod test {
+
+    use super::*;
+
+    #[test]
+    fn test_haversine_distance() {
+        // One degree of latitude along a meridian
+        let a = Point::new(5.0, 52.0);
+        let b = Point::new(5.0, 53.0);
+
+        assert!((haversine_distance(a, b) - 111_195.0).abs() < 1.0);
+        assert_eq!(haversine_distance(a, a), 0.0);
+    }
+
+    #[test]
+    fn test_coordinate_wgs84_roundtrip() {
+        // Near Nijmegen
+        let (lon, lat) = (5.8537, 51.8425);
+
+        let rd = coordinate_wgs84_to_rijksdriehoek(lon, lat);
+        let wgs84 = coordinate_rijksdriehoek_to_wgs84(rd.x, rd.y);
+
+        assert!((wgs84.x - lon).abs() < 1e-5);
+        assert!((wgs84.y - lat).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_validate_polygon() {
+        use geojson::Value;
+
+        let square = Polygon::new(
+            vec![
+                (0.0, 0.0),
+                (10.0, 0.0),
+                (10.0, 10.0),
+                (0.0, 10.0),
+                (0.0, 0.0),
+            ]
+            .into(),
+            vec![],
+        );
+        assert_eq!(validate_polygon(&square), Ok(()));
+
+        let bowtie = Polygon::new(
+            vec![
+                (0.0, 0.0),
+                (10.0, 10.0),
+                (10.0, 0.0),
+                (0.0, 10.0),
+                (0.0, 0.0),
+            ]
+            .into(),
+            vec![],
+        );
+        assert_eq!(
+            validate_polygon(&bowtie),
+            Err(GeometryValidationError::SelfIntersection)
+        );
+
+        let degenerate = Polygon::new(vec![(0.0, 0.0), (10.0, 0.0)].into(), vec![]);
+        assert_eq!(
+            validate_polygon(&degenerate),
+            Err(GeometryValidationError::TooFewVertices(3))
+        );
+
+        let unclosed = Value::Polygon(vec![vec![
+            vec![0.0, 0.0],
+            vec![10.0, 0.0],
+            vec![10.0, 10.0],
+            vec![0.0, 10.0],
+        ]]);
+        assert_eq!(
+            validate_geojson_polygon(&unclosed),
+            Err(GeometryValidationError::UnclosedRing)
+        );
+
+        let two_points = Value::Polygon(vec![vec![vec![0.0, 0.0], vec![10.0, 0.0]]]);
+        assert_eq!(
+            validate_geojson_polygon(&two_points),
+            Err(GeometryValidationError::TooFewVertices(2))
+        );
+
+        assert_eq!(validate_geojson_polygon(&Value::from(&square)), Ok(()));
+    }
+
+    #[test]
+    fn test_simplify_geometry() {
+        use geo::{CoordsIter, LineString};
+
+        // A circle with a radius of 100 m, with a vertex every 0.36 degrees
+        let ring: LineString<f64> = (0..1000)
+            .map(|i| {
+                let angle = i as f64 / 1000.0 * std::f64::consts::TAU;
+                (
+                    187_000.0 + 100.0 * angle.cos(),
+                    428_000.0 + 100.0 * angle.sin(),
+                )
+            })
+            .collect();
+        let circle = geo::Geometry::Polygon(Polygon::new(ring, vec![]));
+
+        let simplified = simplify_geometry(&circle, 0.5);
+
+        let (geo::Geometry::Polygon(before), geo::Geometry::Polygon(after)) =
+            (&circle, &simplified)
+        else {
+            panic!("expected polygons");
+        };
+        assert!(after.coords_count() < before.coords_count() / 10);
+        assert!(
+            (planar_area_m2(after) - planar_area_m2(before)).abs() < planar_area_m2(before) * 0.01
+        );
+    }
+
+    #[test]
+    fn test_round_geometry_coords() {
+        use geo::{polygon, CoordsIter};
+
+        let lot = polygon![
+            (x: 187012.3456, y: 428004.5678),
+            (x: 187045.6789, y: 428004.1234),
+            (x: 187045.9876, y: 428038.4321),
+            (x: 187012.0123, y: 428038.9999),
+        ];
+        let geom = geo::Geometry::Polygon(lot.clone());
+
+        let rounded = round_geometry_coords(&geom, 2);
+
+        let geo::Geometry::Polygon(rounded) = rounded else {
+            panic!("expected a polygon");
+        };
+        assert_eq!(
+            rounded.exterior().0[0],
+            Coord {
+                x: 187012.35,
+                y: 428004.57
+            }
+        );
+        assert!(rounded.coords_iter().all(|coord| {
+            let cents = coord.x * 100.0;
+            (cents - cents.round()).abs() < 1e-6
+        }));
+        assert!((planar_area_m2(&rounded) - planar_area_m2(&lot)).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_looks_like_rijksdriehoek() {
+        // Castellastraat 26, Nijmegen
+        assert!(looks_like_rijksdriehoek(Coord {
+            x: 187_400.0,
+            y: 427_800.0
+        }));
+        assert!(!looks_like_rijksdriehoek(Coord {
+            x: 5.8537,
+            y: 51.8425
+        }));
+    }
+
+    #[test]
+    fn test_bearing() {
+        let nijmegen = Point::new(5.8537, 51.8425);
+
+        // Arnhem is due north, Amsterdam to the northwest
+        assert!(bearing(nijmegen, Point::new(5.8537, 51.9851)) < 1.0);
+        assert!((bearing(nijmegen, Point::new(4.9041, 52.3676)) - 312.0).abs() < 1.0);
+        assert!((bearing(nijmegen, Point::new(5.8537, 51.0)) - 180.0).abs() < 1.0);
+    }
+
+    #[test]
+    fn test_planar_area_m2() {
+        let rect = Rect::new(
+            Coord {
+                x: 187_000.0,
+                y: 428_000.0,
+            },
+            Coord {
+                x: 187_020.0,
+                y: 428_010.0,
+            },
+        );
+
+        assert_eq!(planar_area_m2(&rect.to_polygon()), 200.0);
+    }
+
+    #[test]
+    fn test_union_polygons() {
+        use geo::algorithm::area::Area;
+
+        let rect = |min_x: f64, max_x: f64| {
+            Rect::new(
+                Coord {
+                    x: min_x,
+                    y: 428_000.0,
+                },
+                Coord {
+                    x: max_x,
+                    y: 428_010.0,
+                },
+            )
+            .to_polygon()
+        };
+
+        let touching =
+            union_polygons(&[rect(187_000.0, 187_020.0), rect(187_020.0, 187_030.0)]).unwrap();
+        assert_eq!(touching.0.len(), 1);
+        assert!((touching.unsigned_area() - 300.0).abs() < 1e-6);
+
+        let disjoint =
+            union_polygons(&[rect(187_000.0, 187_020.0), rect(187_040.0, 187_050.0)]).unwrap();
+        assert_eq!(disjoint.0.len(), 2);
+        assert!((disjoint.unsigned_area() - 300.0).abs() < 1e-6);
+
+        assert_eq!(union_polygons(&[]), None);
+    }
+
+    #[test]
+    fn test_expand_to_size() {
+        let rect = Rect::new(
+            Coord {
+                x: 187_000.0,
+                y: 428_000.0,
+            },
+            Coord {
+                x: 187_020.0,
+                y: 428_010.0,
+            },
+        );
+
+        let smaller = expand_to_size(rect, 100.0).unwrap();
+        assert_eq!(smaller.width(), 100.0);
+        assert_eq!(smaller.height(), 100.0);
+        assert_eq!(smaller.center(), rect.center());
+
+        let equal = expand_to_size(rect, 20.0).unwrap();
+        assert_eq!((equal.width(), equal.height()), (20.0, 20.0));
+
+        let larger = expand_to_size(rect, 5.0).unwrap();
+        assert_eq!((larger.width(), larger.height()), (20.0, 20.0));
+        assert_eq!(larger.center(), rect.center());
+
+        assert_eq!(expand_to_size(rect, -1.0), Err(InvalidSize::NegativeSize));
+        assert_eq!(
+            expand_to_size(rect, f64::NAN),
+            Err(InvalidSize::NonFiniteSize)
+        );
+
+        let infinite = Rect::new(
+            rect.min(),
+            Coord {
+                x: f64::INFINITY,
+                y: 428_010.0,
+            },
+        );
+        assert_eq!(
+            expand_to_size(infinite, 100.0),
+            Err(InvalidSize::NonFiniteBbox)
+        );
+    }
+
+    #[test]
+    fn test_bbox_array() {
+        let rect = Rect::new(Coord { x: 5.85, y: 51.84 }, Coord { x: 5.86, y: 51.85 });
+
+        let bbox = rect_to_bbox_array(rect);
+        assert_eq!(bbox, [5.85, 51.84, 5.86, 51.85]);
+        assert_eq!(bbox_array_to_rect(&bbox), Some(rect));
+
+        let bbox_3d = [5.85, 51.84, 0.0, 5.86, 51.85, 12.5];
+        assert_eq!(bbox_array_to_rect(&bbox_3d), Some(rect));
+
+        assert_eq!(bbox_array_to_rect(&[5.85, 51.84, 5.86]), None);
+    }
+
+    #[test]
+    fn test_geojson_feature_bbox() {
+        let points = vec![Point::new(5.85, 51.84), Point::new(5.86, 51.83)];
+
+        let geojson::GeoJson::Feature(feature) = points_to_geojson_multipoint(points.clone(), true)
+        else {
+            panic!("expected a feature");
+        };
+        assert_eq!(feature.bbox, Some(vec![5.85, 51.83, 5.86, 51.84]));
+
+        let geojson::GeoJson::Feature(feature) = points_to_geojson_multipoint(points, false) else {
+            panic!("expected a feature");
+        };
+        assert_eq!(feature.bbox, None);
+
+        let polygon = Rect::new(Coord { x: 0.0, y: 0.0 }, Coord { x: 10.0, y: 5.0 }).to_polygon();
+        let geojson::GeoJson::Feature(feature) =
+            polygons_to_geojson_multipolygon(vec![polygon], true)
+        else {
+            panic!("expected a feature");
+        };
+        assert_eq!(feature.bbox, Some(vec![0.0, 0.0, 10.0, 5.0]));
+    }
+
+    #[test]
+    fn test_display_bbox() {
+        use geo::algorithm::contains::Contains;
+
+        let polygon = Rect::new(
+            Coord {
+                x: 187_000.0,
+                y: 428_000.0,
+            },
+            Coord {
+                x: 187_040.0,
+                y: 428_010.0,
+            },
+        )
+        .to_polygon();
+        let geom = geo::Geometry::Polygon(polygon.clone());
+
+        let bbox = display_bbox(&geom, 0.1).unwrap();
+        assert_eq!(bbox.width(), bbox.height());
+        assert_eq!(bbox.width(), 48.0);
+        assert!(bbox.to_polygon().contains(&polygon));
+
+        let empty = geo::Geometry::MultiPolygon(MultiPolygon::new(vec![]));
+        assert_eq!(display_bbox(&empty, 0.1), None);
+    }
+}